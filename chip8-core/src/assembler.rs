@@ -0,0 +1,739 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::instruction::{encode, Instruction};
+
+/*
+    Assembler for the Cowgod style syntax chip8-disasm writes, so disassembled
+    ROMs can be edited and built again. Output is a ROM to load at 0x200.
+    Octo source, including chip8-disasm --syntax octo listings, isn't taken,
+    that is for reading or for building with Octo itself.
+
+        ; comments run to the end of the line
+        SPEED = 3                   ; constants, also written SPEED EQU 3
+        INCLUDE "sprites.asm"       ; paths are relative to the including file
+
+        MACRO move reg, amount      ; macros take comma separated arguments,
+            ADD reg, amount         ; \@ in a macro body becomes a number
+        ENDM                        ; unique to each expansion
+
+        start:  LD V0, SPEED * 2
+                move V0, -1
+                LD I, ball
+                DRW V1, V2, ball_end - ball
+                JP start
+        ball:   SPRITE .####.
+                SPRITE ######       ; sprite rows, # or 1 for set pixels
+        ball_end:
+                DB #FF, %1010, 'A', "text"
+                DW start
+                ORG #300            ; pads with zeros up to the address
+
+    Numbers are decimal, #hex, 0xhex, $hex, %binary, 0bbinary or 'c', and
+    expressions take + - * / % & | ^ << >> ~ and parentheses. $ alone is
+    the address of the current line.
+*/
+
+const ORIGIN: usize = 0x200;
+const MAX_ADDRESS: usize = 0xFFFF;
+// deep enough for any real program, shallow enough to catch a file including itself
+const MAX_NESTING: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssemblyError {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+impl Error for AssemblyError {}
+
+/*
+    Assemble source text, includes are looked up from the working directory
+*/
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssemblyError> {
+    let mut preprocessor = Preprocessor::new();
+    preprocessor.read(source, "<source>", Path::new("."), 0)?;
+    build(&preprocessor.lines)
+}
+
+pub fn assemble_file(path: &str) -> Result<Vec<u8>, AssemblyError> {
+    let mut preprocessor = Preprocessor::new();
+    let source: String = std::fs::read_to_string(path).map_err(|e| AssemblyError {
+        file: String::from(path),
+        line: 0,
+        message: format!("failed to read file: {}", e),
+    })?;
+    let directory: &Path = Path::new(path).parent().unwrap_or_else(|| Path::new("."));
+    preprocessor.read(&source, path, directory, 0)?;
+    build(&preprocessor.lines)
+}
+
+/*
+    A line left after includes and macros are expanded, comment removed
+*/
+#[derive(Debug, Clone)]
+struct Line {
+    file: String,
+    number: usize,
+    text: String,
+}
+
+impl Line {
+    fn error(&self, message: String) -> AssemblyError {
+        AssemblyError { file: self.file.clone(), line: self.number, message }
+    }
+}
+
+struct Macro {
+    parameters: Vec<String>,
+    body: Vec<Line>,
+}
+
+struct Preprocessor {
+    lines: Vec<Line>,
+    macros: HashMap<String, Macro>,
+    expansions: usize,
+}
+
+impl Preprocessor {
+    fn new() -> Preprocessor {
+        Preprocessor { lines: Vec::new(), macros: HashMap::new(), expansions: 0 }
+    }
+
+    fn read(&mut self, source: &str, file: &str, directory: &Path, depth: usize) -> Result<(), AssemblyError> {
+        let lines: Vec<Line> = source.lines().enumerate()
+            .map(|(index, text)| Line { file: String::from(file), number: index + 1, text: String::from(strip_comment(text).trim()) })
+            .collect();
+
+        let mut index: usize = 0;
+        while index < lines.len() {
+            let line: &Line = &lines[index];
+            index += 1;
+            let (keyword, rest) = split_word(&line.text);
+
+            if keyword.eq_ignore_ascii_case("INCLUDE") {
+                if depth >= MAX_NESTING {
+                    return Err(line.error(String::from("includes nested too deeply")));
+                }
+                let name: &str = unquote(rest).ok_or_else(|| line.error(String::from("INCLUDE needs a quoted file name")))?;
+                let path: PathBuf = directory.join(name);
+                let text: String = std::fs::read_to_string(&path)
+                    .map_err(|e| line.error(format!("failed to include {}: {}", path.display(), e)))?;
+                let included_directory: PathBuf = path.parent().map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+                self.read(&text, &path.display().to_string(), &included_directory, depth + 1)?;
+            } else if keyword.eq_ignore_ascii_case("MACRO") {
+                let (name, parameters) = split_word(rest);
+                if name.is_empty() {
+                    return Err(line.error(String::from("MACRO needs a name")));
+                }
+                let parameters: Vec<String> = split_operands(parameters).into_iter().filter(|parameter| !parameter.is_empty()).collect();
+                let start: usize = index;
+                while index < lines.len() && !split_word(&lines[index].text).0.eq_ignore_ascii_case("ENDM") {
+                    index += 1;
+                }
+                if index == lines.len() {
+                    return Err(line.error(format!("MACRO {} has no ENDM", name)));
+                }
+                let body: Vec<Line> = lines[start..index].to_vec();
+                self.macros.insert(name.to_ascii_lowercase(), Macro { parameters, body });
+                index += 1;
+            } else {
+                self.expand(line.clone(), depth)?;
+            }
+        }
+        Ok(())
+    }
+
+    // macro invocations are replaced by their body, anything else is kept
+    fn expand(&mut self, line: Line, depth: usize) -> Result<(), AssemblyError> {
+        let (label, statement) = split_label(&line.text);
+        let (name, arguments) = split_word(statement);
+        let definition: &Macro = match self.macros.get(&name.to_ascii_lowercase()) {
+            Some(definition) => definition,
+            None => {
+                self.lines.push(line);
+                return Ok(());
+            }
+        };
+        if depth >= MAX_NESTING {
+            return Err(line.error(String::from("macros nested too deeply")));
+        }
+
+        let arguments: Vec<String> = split_operands(arguments).into_iter().filter(|argument| !argument.is_empty()).collect();
+        if arguments.len() != definition.parameters.len() {
+            return Err(line.error(format!("macro {} takes {} arguments, got {}", name, definition.parameters.len(), arguments.len())));
+        }
+        self.expansions += 1;
+        let unique: String = format!("_{}", self.expansions);
+        let body: Vec<Line> = definition.body.iter().map(|body_line| Line {
+            file: body_line.file.clone(),
+            number: body_line.number,
+            text: substitute(&body_line.text, &definition.parameters, &arguments).replace("\\@", &unique),
+        }).collect();
+
+        if let Some(label) = label {
+            self.lines.push(Line { text: format!("{}:", label), ..line.clone() });
+        }
+        for body_line in body {
+            self.expand(body_line, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Statement {
+    Instruction { mnemonic: String, operands: Vec<String> },
+    Bytes(Vec<String>),
+    Words(Vec<String>),
+    Sprite(String),
+    Org(usize),
+}
+
+/*
+    Two passes: the first places every line and learns the labels, the second
+    evaluates operands now that every label is known
+*/
+fn build(lines: &[Line]) -> Result<Vec<u8>, AssemblyError> {
+    let mut symbols: HashMap<String, i64> = HashMap::new();
+    let mut placed: Vec<(&Line, usize, Statement)> = Vec::new();
+    let mut address: usize = ORIGIN;
+
+    for line in lines {
+        if looks_like_octo(&line.text) {
+            return Err(line.error(String::from("this looks like Octo syntax, the assembler takes Cowgod syntax (chip8-disasm --syntax cowgod)")));
+        }
+        let (label, statement) = split_label(&line.text);
+        if let Some(label) = label {
+            define(&mut symbols, label, address as i64, line)?;
+        }
+        let (keyword, rest) = split_word(statement);
+        if keyword.is_empty() {
+            continue;
+        }
+
+        // NAME = value and NAME EQU value
+        let (second, value) = split_word(rest);
+        if second == "=" || second.eq_ignore_ascii_case("EQU") {
+            let value: i64 = evaluate(value, &symbols, address).map_err(|e| line.error(e))?;
+            define(&mut symbols, keyword, value, line)?;
+            continue;
+        }
+
+        let statement: Statement = match keyword.to_ascii_uppercase().as_str() {
+            "DB" => Statement::Bytes(split_operands(rest)),
+            "DW" => Statement::Words(split_operands(rest)),
+            "SPRITE" => Statement::Sprite(String::from(rest)),
+            "ORG" => {
+                let target: i64 = evaluate(rest, &symbols, address).map_err(|e| line.error(e))?;
+                if target < address as i64 || target > MAX_ADDRESS as i64 {
+                    return Err(line.error(format!("ORG {:#x} is behind the current address {:#x} or out of memory", target, address)));
+                }
+                Statement::Org(target as usize)
+            }
+            mnemonic => Statement::Instruction { mnemonic: String::from(mnemonic), operands: split_operands(rest) },
+        };
+        let size: usize = statement_size(&statement, address).map_err(|e| line.error(e))?;
+        placed.push((line, address, statement));
+        address += size;
+        if address > MAX_ADDRESS + 1 {
+            return Err(line.error(String::from("program doesn't fit in memory")));
+        }
+    }
+
+    let mut rom: Vec<u8> = Vec::new();
+    for (line, address, statement) in placed {
+        let bytes: Vec<u8> = emit(&statement, address, &symbols).map_err(|e| line.error(e))?;
+        rom.extend_from_slice(&bytes);
+    }
+    Ok(rom)
+}
+
+/*
+    Octo listings from chip8-disasm --syntax octo would otherwise fail with
+    confusing errors: ": name" labels, "# comments", ":=" assignments and
+    "sprite vx vy n", which reads like a SPRITE row
+*/
+fn looks_like_octo(text: &str) -> bool {
+    let (keyword, rest) = split_word(text);
+    let sprite_registers: bool = keyword.eq_ignore_ascii_case("SPRITE")
+        && matches!(Operand::parse(split_word(rest).0), Operand::V(_));
+    keyword == ":" || keyword == "#" || text.contains(":=") || sprite_registers
+}
+
+fn define(symbols: &mut HashMap<String, i64>, name: &str, value: i64, line: &Line) -> Result<(), AssemblyError> {
+    let valid: bool = name.chars().next().is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if !valid {
+        return Err(line.error(format!("'{}' is not a valid name", name)));
+    }
+    if symbols.insert(String::from(name), value).is_some() {
+        return Err(line.error(format!("'{}' is defined twice", name)));
+    }
+    Ok(())
+}
+
+fn statement_size(statement: &Statement, address: usize) -> Result<usize, String> {
+    Ok(match statement {
+        Statement::Instruction { mnemonic, operands } => {
+            let long: bool = mnemonic == "LD" && operands.get(1).is_some_and(|operand| is_long(operand).is_some());
+            if long { 4 } else { 2 }
+        }
+        Statement::Bytes(items) => {
+            let mut size: usize = 0;
+            for item in items {
+                size += match item.starts_with('"') {
+                    true => unquote(item).ok_or_else(|| format!("unterminated string {}", item))?.len(),
+                    false => 1,
+                };
+            }
+            size
+        }
+        Statement::Words(items) => items.len() * 2,
+        Statement::Sprite(row) => sprite_row(row)?.len(),
+        Statement::Org(target) => target - address,
+    })
+}
+
+fn emit(statement: &Statement, address: usize, symbols: &HashMap<String, i64>) -> Result<Vec<u8>, String> {
+    let value = |text: &str| evaluate(text, symbols, address);
+    match statement {
+        Statement::Instruction { mnemonic, operands } => {
+            let operands: Vec<Operand> = operands.iter().map(|operand| Operand::parse(operand)).collect();
+            let (instruction, long) = instruction(mnemonic, &operands, &value)?;
+            let mut bytes: Vec<u8> = encode(instruction).to_be_bytes().to_vec();
+            if let Some(long) = long {
+                bytes.extend_from_slice(&long.to_be_bytes());
+            }
+            Ok(bytes)
+        }
+        Statement::Bytes(items) => {
+            let mut bytes: Vec<u8> = Vec::new();
+            for item in items {
+                match unquote(item) {
+                    Some(text) if item.starts_with('"') => bytes.extend_from_slice(text.as_bytes()),
+                    _ => bytes.push(byte(value(item)?)?),
+                }
+            }
+            Ok(bytes)
+        }
+        Statement::Words(items) => {
+            let mut bytes: Vec<u8> = Vec::new();
+            for item in items {
+                bytes.extend_from_slice(&word(value(item)?)?.to_be_bytes());
+            }
+            Ok(bytes)
+        }
+        Statement::Sprite(row) => sprite_row(row),
+        Statement::Org(target) => Ok(vec![0; target - address]),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Operand {
+    V(u8),
+    I,
+    // [I]
+    Memory,
+    Dt,
+    St,
+    K,
+    F,
+    Hf,
+    B,
+    R,
+    Long(String),
+    Value(String),
+}
+
+impl Operand {
+    fn parse(text: &str) -> Operand {
+        let upper: String = text.to_ascii_uppercase();
+        if let Some(rest) = is_long(text) {
+            return Operand::Long(String::from(rest));
+        }
+        match upper.as_str() {
+            "I" => Operand::I,
+            "[I]" => Operand::Memory,
+            "DT" => Operand::Dt,
+            "ST" => Operand::St,
+            "K" => Operand::K,
+            "F" => Operand::F,
+            "HF" => Operand::Hf,
+            "B" => Operand::B,
+            "R" => Operand::R,
+            _ if upper.len() == 2 && upper.starts_with('V') => match u8::from_str_radix(&upper[1..], 16) {
+                Ok(index) => Operand::V(index),
+                Err(_) => Operand::Value(String::from(text)),
+            },
+            _ => Operand::Value(String::from(text)),
+        }
+    }
+}
+
+fn is_long(text: &str) -> Option<&str> {
+    let (keyword, rest) = split_word(text);
+    match keyword.eq_ignore_ascii_case("LONG") {
+        true => Some(rest),
+        false => None,
+    }
+}
+
+/*
+    The instruction for a mnemonic and its operands, plus the address word
+    that follows LD I, LONG
+*/
+fn instruction(mnemonic: &str, operands: &[Operand], value: &dyn Fn(&str) -> Result<i64, String>) -> Result<(Instruction, Option<u16>), String> {
+    use Operand::*;
+    let address = |text: &str| -> Result<u16, String> { in_range(value(text)?, 0, 0xFFF, "an address") };
+    let nibble = |text: &str| -> Result<u8, String> { Ok(in_range(value(text)?, 0, 0xF, "a nibble")? as u8) };
+    let immediate = |text: &str| -> Result<u8, String> { byte(value(text)?) };
+
+    let instruction: Instruction = match (mnemonic, operands) {
+        ("CLS", []) => Instruction::Clear,
+        ("RET", []) => Instruction::Return,
+        ("SYS", [Value(a)]) => Instruction::Sys { nnn: address(a)? },
+        ("SCD", [Value(n)]) => Instruction::ScrollDown { n: nibble(n)? },
+        ("SCR", []) => Instruction::ScrollRight,
+        ("SCL", []) => Instruction::ScrollLeft,
+        ("EXIT", []) => Instruction::Exit,
+        ("LOW", []) => Instruction::Lores,
+        ("HIGH", []) => Instruction::Hires,
+        ("JP", [Value(a)]) => Instruction::Jump { nnn: address(a)? },
+        ("JP", [V(0), Value(a)]) => Instruction::JumpOffset { nnn: address(a)? },
+        ("CALL", [Value(a)]) => Instruction::Call { nnn: address(a)? },
+        ("SE", [V(x), V(y)]) => Instruction::SkipIfRegistersEqual { x: *x, y: *y },
+        ("SE", [V(x), Value(b)]) => Instruction::SkipIfEqual { x: *x, nn: immediate(b)? },
+        ("SNE", [V(x), V(y)]) => Instruction::SkipIfRegistersNotEqual { x: *x, y: *y },
+        ("SNE", [V(x), Value(b)]) => Instruction::SkipIfNotEqual { x: *x, nn: immediate(b)? },
+        ("SAVE", [V(x), V(y)]) => Instruction::SaveRange { x: *x, y: *y },
+        ("LOAD", [V(x), V(y)]) => Instruction::LoadRange { x: *x, y: *y },
+        ("LD", [V(x), V(y)]) => Instruction::Copy { x: *x, y: *y },
+        ("LD", [V(x), Value(b)]) => Instruction::Set { x: *x, nn: immediate(b)? },
+        ("LD", [I, Value(a)]) => Instruction::SetIndex { nnn: address(a)? },
+        ("LD", [I, Long(a)]) => {
+            let target: u16 = word(value(a)?)?;
+            return Ok((Instruction::SetIndexLong, Some(target)));
+        }
+        ("LD", [V(x), Dt]) => Instruction::GetDelay { x: *x },
+        ("LD", [V(x), K]) => Instruction::WaitKey { x: *x },
+        ("LD", [Dt, V(x)]) => Instruction::SetDelay { x: *x },
+        ("LD", [St, V(x)]) => Instruction::SetSound { x: *x },
+        ("LD", [F, V(x)]) => Instruction::FontCharacter { x: *x },
+        ("LD", [Hf, V(x)]) => Instruction::BigFontCharacter { x: *x },
+        ("LD", [B, V(x)]) => Instruction::Bcd { x: *x },
+        ("LD", [Memory, V(x)]) => Instruction::Store { x: *x },
+        ("LD", [V(x), Memory]) => Instruction::Load { x: *x },
+        ("LD", [R, V(x)]) => Instruction::SaveFlags { x: *x },
+        ("LD", [V(x), R]) => Instruction::LoadFlags { x: *x },
+        ("ADD", [V(x), V(y)]) => Instruction::Add { x: *x, y: *y },
+        ("ADD", [V(x), Value(b)]) => Instruction::AddImmediate { x: *x, nn: immediate(b)? },
+        ("ADD", [I, V(x)]) => Instruction::AddIndex { x: *x },
+        ("OR", [V(x), V(y)]) => Instruction::Or { x: *x, y: *y },
+        ("AND", [V(x), V(y)]) => Instruction::And { x: *x, y: *y },
+        ("XOR", [V(x), V(y)]) => Instruction::Xor { x: *x, y: *y },
+        ("SUB", [V(x), V(y)]) => Instruction::Subtract { x: *x, y: *y },
+        ("SUBN", [V(x), V(y)]) => Instruction::SubtractReversed { x: *x, y: *y },
+        ("SHR", [V(x)]) => Instruction::ShiftRight { x: *x, y: *x },
+        ("SHR", [V(x), V(y)]) => Instruction::ShiftRight { x: *x, y: *y },
+        ("SHL", [V(x)]) => Instruction::ShiftLeft { x: *x, y: *x },
+        ("SHL", [V(x), V(y)]) => Instruction::ShiftLeft { x: *x, y: *y },
+        ("RND", [V(x), Value(b)]) => Instruction::Random { x: *x, nn: immediate(b)? },
+        ("DRW", [V(x), V(y), Value(n)]) => Instruction::Draw { x: *x, y: *y, n: nibble(n)? },
+        ("SKP", [V(x)]) => Instruction::SkipIfKey { x: *x },
+        ("SKNP", [V(x)]) => Instruction::SkipIfNotKey { x: *x },
+        ("PLANE", [Value(n)]) => Instruction::SelectPlanes { n: nibble(n)? },
+        ("AUDIO", []) => Instruction::LoadAudio,
+        ("PITCH", [V(x)]) => Instruction::SetPitch { x: *x },
+        _ if KNOWN_MNEMONICS.contains(&mnemonic) => return Err(format!("bad operands for {}", mnemonic)),
+        _ => return Err(format!("unknown instruction '{}'", mnemonic)),
+    };
+    Ok((instruction, None))
+}
+
+const KNOWN_MNEMONICS: [&str;31] = [
+    "CLS", "RET", "SYS", "SCD", "SCR", "SCL", "EXIT", "LOW", "HIGH", "JP", "CALL", "SE", "SNE", "SAVE",
+    "LOAD", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SUBN", "SHR", "SHL", "RND", "DRW", "SKP", "SKNP",
+    "PLANE", "AUDIO", "PITCH",
+];
+
+fn in_range(value: i64, min: i64, max: i64, what: &str) -> Result<u16, String> {
+    if value < min || value > max {
+        return Err(format!("{} doesn't fit in {}", value, what));
+    }
+    Ok(value as u16)
+}
+
+// bytes may be written signed, ADD V0, -1 adds 0xFF
+fn byte(value: i64) -> Result<u8, String> {
+    if !(-0x80..=0xFF).contains(&value) {
+        return Err(format!("{} doesn't fit in a byte", value));
+    }
+    Ok(value as u8)
+}
+
+fn word(value: i64) -> Result<u16, String> {
+    if !(-0x8000..=0xFFFF).contains(&value) {
+        return Err(format!("{} doesn't fit in a word", value));
+    }
+    Ok(value as u16)
+}
+
+/*
+    One row of sprite art, eight pixels per byte
+*/
+fn sprite_row(row: &str) -> Result<Vec<u8>, String> {
+    let pixels: Vec<char> = row.chars().filter(|c| !c.is_whitespace()).collect();
+    if pixels.is_empty() || !pixels.len().is_multiple_of(8) {
+        return Err(format!("sprite rows are 8 or 16 pixels wide, '{}' has {}", row, pixels.len()));
+    }
+    let mut bytes: Vec<u8> = Vec::new();
+    for chunk in pixels.chunks(8) {
+        let mut value: u8 = 0;
+        for (bit, pixel) in chunk.iter().enumerate() {
+            match pixel {
+                '#' | '1' | 'X' | 'x' => value |= 0x80 >> bit,
+                '.' | '0' | '_' => {}
+                _ => return Err(format!("'{}' is not a sprite pixel, use # or .", pixel)),
+            }
+        }
+        bytes.push(value);
+    }
+    Ok(bytes)
+}
+
+fn strip_comment(text: &str) -> &str {
+    let mut quote: Option<char> = None;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (None, ';') => return &text[..index],
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            _ => {}
+        }
+    }
+    text
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    let text: &str = text.trim();
+    match text.find(char::is_whitespace) {
+        Some(index) => (&text[..index], text[index..].trim()),
+        None => (text, ""),
+    }
+}
+
+// "label: rest", or just "label:"
+fn split_label(text: &str) -> (Option<&str>, &str) {
+    let (first, rest) = split_word(text);
+    match first.strip_suffix(':') {
+        Some(label) if !label.is_empty() => (Some(label), rest),
+        _ => (None, text.trim()),
+    }
+}
+
+// commas outside of quotes and parentheses separate operands
+fn split_operands(text: &str) -> Vec<String> {
+    let mut operands: Vec<String> = Vec::new();
+    let mut current: String = String::new();
+    let mut depth: i32 = 0;
+    let mut quote: Option<char> = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (None, ',') if depth == 0 => {
+                operands.push(String::from(current.trim()));
+                current.clear();
+                continue;
+            }
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() || !operands.is_empty() {
+        operands.push(String::from(current.trim()));
+    }
+    operands
+}
+
+fn unquote(text: &str) -> Option<&str> {
+    let text: &str = text.trim();
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        Some(&text[1..text.len() - 1])
+    } else {
+        None
+    }
+}
+
+// replace whole word parameters with the macro arguments
+fn substitute(text: &str, parameters: &[String], arguments: &[String]) -> String {
+    let mut out: String = String::new();
+    let mut word: String = String::new();
+    let flush = |word: &mut String, out: &mut String| {
+        match parameters.iter().position(|parameter| parameter == word.as_str()) {
+            Some(index) => out.push_str(&arguments[index]),
+            None => out.push_str(word),
+        }
+        word.clear();
+    };
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+/*
+    Expression evaluation by recursive descent, lowest precedence first:
+    |, ^, &, shifts, + and -, * / and %, then unary operators
+*/
+fn evaluate(text: &str, symbols: &HashMap<String, i64>, address: usize) -> Result<i64, String> {
+    let mut parser = ExpressionParser { chars: text.chars().collect(), position: 0, symbols, address };
+    let value: i64 = parser.binary(0)?;
+    parser.skip_spaces();
+    if parser.position < parser.chars.len() {
+        return Err(format!("unexpected '{}' in expression '{}'", parser.chars[parser.position], text.trim()));
+    }
+    Ok(value)
+}
+
+const BINARY_OPERATORS: [&[&str];6] = [&["|"], &["^"], &["&"], &["<<", ">>"], &["+", "-"], &["*", "/", "%"]];
+
+struct ExpressionParser<'a> {
+    chars: Vec<char>,
+    position: usize,
+    symbols: &'a HashMap<String, i64>,
+    address: usize,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn skip_spaces(&mut self) {
+        while self.chars.get(self.position).is_some_and(|c| c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn take(&mut self, symbol: &str) -> bool {
+        self.skip_spaces();
+        let end: usize = self.position + symbol.len();
+        if end <= self.chars.len() && self.chars[self.position..end].iter().copied().eq(symbol.chars()) {
+            self.position = end;
+            return true;
+        }
+        false
+    }
+
+    fn binary(&mut self, level: usize) -> Result<i64, String> {
+        if level == BINARY_OPERATORS.len() {
+            return self.unary();
+        }
+        let mut value: i64 = self.binary(level + 1)?;
+        'operators: loop {
+            for operator in BINARY_OPERATORS[level] {
+                if self.take(operator) {
+                    let right: i64 = self.binary(level + 1)?;
+                    value = match *operator {
+                        "|" => value | right,
+                        "^" => value ^ right,
+                        "&" => value & right,
+                        "<<" => value.checked_shl(right as u32).unwrap_or(0),
+                        ">>" => value.checked_shr(right as u32).unwrap_or(0),
+                        "+" => value.wrapping_add(right),
+                        "-" => value.wrapping_sub(right),
+                        "*" => value.wrapping_mul(right),
+                        _ if right == 0 => return Err(String::from("division by zero")),
+                        "/" => value.checked_div(right).ok_or("division overflows")?,
+                        _ => value.checked_rem(right).ok_or("division overflows")?,
+                    };
+                    continue 'operators;
+                }
+            }
+            return Ok(value);
+        }
+    }
+
+    fn unary(&mut self) -> Result<i64, String> {
+        if self.take("-") {
+            return self.unary()?.checked_neg().ok_or_else(|| String::from("negation overflows"));
+        }
+        if self.take("~") {
+            return Ok(!self.unary()?);
+        }
+        if self.take("+") {
+            return self.unary();
+        }
+        if self.take("(") {
+            let value: i64 = self.binary(0)?;
+            if !self.take(")") {
+                return Err(String::from("missing )"));
+            }
+            return Ok(value);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<i64, String> {
+        self.skip_spaces();
+        let start: usize = self.position;
+        if self.take("'") {
+            let c: char = *self.chars.get(self.position).ok_or("unterminated character")?;
+            self.position += 1;
+            if !self.take("'") {
+                return Err(String::from("unterminated character"));
+            }
+            return Ok(c as i64);
+        }
+        while self.chars.get(self.position).is_some_and(|c| c.is_ascii_alphanumeric() || "_.#$%".contains(*c)) {
+            self.position += 1;
+        }
+        let token: String = self.chars[start..self.position].iter().collect();
+        if token.is_empty() {
+            return Err(match self.chars.get(self.position) {
+                Some(c) => format!("unexpected '{}' in expression", c),
+                None => String::from("missing value"),
+            });
+        }
+        if token == "$" {
+            return Ok(self.address as i64);
+        }
+        if let Some(value) = number(&token) {
+            return Ok(value);
+        }
+        if token.starts_with(|c: char| c.is_ascii_digit() || "#$%".contains(c)) {
+            return Err(format!("bad number '{}'", token));
+        }
+        self.symbols.get(&token).copied().ok_or_else(|| format!("unknown name '{}'", token))
+    }
+}
+
+fn number(token: &str) -> Option<i64> {
+    let lower: String = token.to_ascii_lowercase();
+    let (digits, radix) = if let Some(hex) = lower.strip_prefix("0x").or_else(|| lower.strip_prefix('#')).or_else(|| lower.strip_prefix('$')) {
+        (hex, 16)
+    } else if let Some(binary) = lower.strip_prefix("0b").or_else(|| lower.strip_prefix('%')) {
+        (binary, 2)
+    } else {
+        (lower.as_str(), 10)
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    i64::from_str_radix(digits, radix).ok()
+}
@@ -0,0 +1,168 @@
+use std::f32::consts::PI;
+
+/*
+    Shape of the tone played while the sound timer is running
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Sine,
+    Triangle,
+    Sawtooth,
+}
+
+impl Waveform {
+    pub fn from_name(name: &str) -> Option<Waveform> {
+        match name.to_ascii_lowercase().as_str() {
+            "square" => Some(Waveform::Square),
+            "sine" => Some(Waveform::Sine),
+            "triangle" => Some(Waveform::Triangle),
+            "saw" | "sawtooth" => Some(Waveform::Sawtooth),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioConfig {
+    // tone frequency in Hz
+    pub frequency: f32,
+    // 0.0 - 1.0
+    pub volume: f32,
+    pub waveform: Waveform,
+    pub muted: bool,
+}
+
+impl Default for AudioConfig {
+    fn default() -> AudioConfig {
+        AudioConfig {
+            frequency: 440.0,
+            volume: 0.25,
+            waveform: Waveform::Square,
+            muted: false,
+        }
+    }
+}
+
+/*
+    XO-CHIP sound: 128 one bit samples, looped at rate samples per second
+    while the buzzer sounds
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pattern {
+    pub bits: [u8;16],
+    pub rate: f32,
+}
+
+impl Pattern {
+    // the sample at a position counted in samples from the start of the loop
+    pub fn bit(&self, position: usize) -> bool {
+        let position: usize = position % 128;
+        self.bits[position / 8] & (0x80 >> (position % 8)) != 0
+    }
+}
+
+/*
+    Anything that can be told once per frame whether the buzzer should sound
+    and what it plays: the configured tone, or the program's pattern
+*/
+pub trait AudioSink {
+    fn set_playing(&mut self, frame: u64, playing: bool);
+
+    fn set_pattern(&mut self, frame: u64, pattern: Option<Pattern>);
+}
+
+/*
+    Audio sink for headless runs. Plays nothing and records the frames where the
+    buzzer turned on or off, and where the pattern changed, so tests can check
+    the sound timer and XO-CHIP audio behaviour.
+*/
+#[derive(Default)]
+pub struct NullAudio {
+    playing: bool,
+    timeline: Vec<(u64, bool)>,
+    pattern: Option<Pattern>,
+    patterns: Vec<(u64, Option<Pattern>)>,
+}
+
+impl NullAudio {
+    pub fn new() -> NullAudio {
+        NullAudio::default()
+    }
+
+    // (frame, playing) for every change of state
+    pub fn timeline(&self) -> &[(u64, bool)] {
+        &self.timeline
+    }
+
+    // (frame, pattern) for every change of pattern or rate
+    pub fn patterns(&self) -> &[(u64, Option<Pattern>)] {
+        &self.patterns
+    }
+}
+
+impl AudioSink for NullAudio {
+    fn set_playing(&mut self, frame: u64, playing: bool) {
+        if playing != self.playing {
+            self.playing = playing;
+            self.timeline.push((frame, playing));
+        }
+    }
+
+    fn set_pattern(&mut self, frame: u64, pattern: Option<Pattern>) {
+        if pattern != self.pattern {
+            self.pattern = pattern;
+            self.patterns.push((frame, pattern));
+        }
+    }
+}
+
+/*
+    Sample generator behind the SDL audio callback, kept free of SDL so the
+    waveforms can be reused by other frontends.
+*/
+pub struct Oscillator {
+    pub config: AudioConfig,
+    pub playing: bool,
+    // played instead of the tone when set
+    pub pattern: Option<Pattern>,
+    sample_rate: f32,
+    phase: f32,
+}
+
+impl Oscillator {
+    pub fn new(config: AudioConfig, sample_rate: f32) -> Oscillator {
+        Oscillator {
+            config,
+            playing: false,
+            pattern: None,
+            sample_rate,
+            phase: 0.0,
+        }
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        if !self.playing || self.config.muted {
+            // restart the wave on the next beep so it doesn't start with a click
+            self.phase = 0.0;
+            return 0.0;
+        }
+
+        if let Some(pattern) = self.pattern {
+            // phase counts pattern samples here, the pattern loops every 128
+            let sample: f32 = if pattern.bit(self.phase as usize) { 1.0 } else { -1.0 };
+            self.phase = (self.phase + pattern.rate / self.sample_rate) % 128.0;
+            return sample * self.config.volume;
+        }
+
+        let sample: f32 = match self.config.waveform {
+            Waveform::Square => if self.phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sine => (self.phase * 2.0 * PI).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (self.phase - 0.5).abs(),
+            Waveform::Sawtooth => 2.0 * self.phase - 1.0,
+        };
+
+        self.phase = (self.phase + self.config.frequency / self.sample_rate) % 1.0;
+        sample * self.config.volume
+    }
+}
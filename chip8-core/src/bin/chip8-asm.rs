@@ -0,0 +1,59 @@
+use std::path::Path;
+use std::process;
+
+use chip8_core::assembler::assemble_file;
+
+const USAGE: &str = "usage: chip8-asm <source> [options]
+
+  --output FILE       write the rom to FILE (default: the source with a .ch8 extension)";
+
+fn fail(message: &str) -> ! {
+    eprintln!("chip8-asm: {}", message);
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn next_value(args: &mut dyn Iterator<Item = String>, flag: &str) -> String {
+    match args.next() {
+        Some(value) => value,
+        None => fail(&format!("{} needs a value", flag)),
+    }
+}
+
+fn main() {
+    let mut source: Option<String> = None;
+    let mut output: Option<String> = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = Some(next_value(&mut args, &arg)),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with("--") => fail(&format!("unknown option '{}'", arg)),
+            _ if source.is_none() => source = Some(arg),
+            _ => fail(&format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    let path: String = source.unwrap_or_else(|| fail("no source given"));
+    let output: String = output.unwrap_or_else(|| Path::new(&path).with_extension("ch8").display().to_string());
+    if output == path {
+        fail("the output would overwrite the source, use --output");
+    }
+
+    let rom: Vec<u8> = match assemble_file(&path) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("chip8-asm: {}", e);
+            process::exit(1);
+        }
+    };
+    if let Err(e) = std::fs::write(&output, &rom) {
+        eprintln!("chip8-asm: failed to write {}: {}", output, e);
+        process::exit(1);
+    }
+    println!("{}: {} bytes", output, rom.len());
+}
@@ -0,0 +1,66 @@
+use std::process;
+
+use chip8_core::disassembler::{disassemble, Syntax};
+
+const USAGE: &str = "usage: chip8-disasm <rom> [options]
+
+  --syntax NAME       cowgod or octo (default cowgod), only cowgod listings
+                      assemble again with chip8-asm
+  --output FILE       write the listing to FILE instead of stdout";
+
+fn fail(message: &str) -> ! {
+    eprintln!("chip8-disasm: {}", message);
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn next_value(args: &mut dyn Iterator<Item = String>, flag: &str) -> String {
+    match args.next() {
+        Some(value) => value,
+        None => fail(&format!("{} needs a value", flag)),
+    }
+}
+
+fn main() {
+    let mut rom: Option<String> = None;
+    let mut syntax: Syntax = Syntax::Cowgod;
+    let mut output: Option<String> = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--syntax" => {
+                let name: String = next_value(&mut args, &arg);
+                syntax = Syntax::from_name(&name).unwrap_or_else(|| fail(&format!("unknown syntax '{}'", name)));
+            }
+            "--output" => output = Some(next_value(&mut args, &arg)),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with("--") => fail(&format!("unknown option '{}'", arg)),
+            _ if rom.is_none() => rom = Some(arg),
+            _ => fail(&format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    let path: String = rom.unwrap_or_else(|| fail("no rom given"));
+    let bytes: Vec<u8> = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("chip8-disasm: failed to read {}: {}", path, e);
+            process::exit(1);
+        }
+    };
+
+    let listing: String = disassemble(&bytes, syntax);
+    match output {
+        Some(output) => {
+            if let Err(e) = std::fs::write(&output, listing) {
+                eprintln!("chip8-disasm: failed to write {}: {}", output, e);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", listing),
+    }
+}
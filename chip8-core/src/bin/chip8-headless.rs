@@ -0,0 +1,265 @@
+use std::process;
+use std::time::Duration;
+
+use chip8_core::chip8::{Chip8, Mode};
+use chip8_core::headless::{HeadlessRunner, InputScript, StopCondition, StopReason};
+use chip8_core::movie::Movie;
+use chip8_core::watch::{WatchAction, Watchpoint};
+use chip8_core::quirks::Quirks;
+use chip8_core::random::RandomKind;
+use chip8_core::trace::{TraceFilter, TraceFormat, Tracer};
+
+const USAGE: &str = "usage: chip8-headless <rom> [options]
+
+  --frames N          stop after N frames (default 600 when no other limit is given)
+  --until-loop        stop when the program jumps to itself
+  --until-opcode XXXX stop before executing the given opcode (hex)
+  --timeout SECS      stop after SECS seconds of wall clock time
+  --watch SPEC        log accesses to memory, SPEC is ADDR[-END][:r|w|x] in hex, e.g. 3a0-3a2:w,
+                      add :break to stop the run at the first access instead
+  --input FILE        scripted key presses, lines of '<frame> <down|up> <key>'
+  --play FILE         replay a movie recorded with the emulator's --record, its settings
+                      override the options below and the run ends with the recording
+  --quirks NAME       vip, chip48, schip, xochip or modern
+  --mode NAME         chip8, schip or xochip
+  --speed N           instructions per second
+  --seed N            seed for CXNN, runs are repeatable with the same seed (default 0)
+  --rng NAME          xorshift or lcg
+  --text FILE         write the framebuffer as text ('-' for stdout)
+  --png FILE          write the framebuffer as a PNG
+  --png-scale N       PNG pixels per CHIP-8 pixel (default 4)
+  --json FILE         write registers and the stop reason as JSON ('-' for stdout)
+  --trace FILE        write a record of every instruction run to FILE
+  --trace-format NAME text or binary (default text)
+  --trace-pc RANGE    only trace instructions at these addresses, e.g. 200-2ff (hex)
+  --trace-ops DIGITS  only trace opcodes starting with these hex digits, e.g. 8,d
+  --trace-frames A-B  only trace frames A to B, B may be left out";
+
+fn fail(message: &str) -> ! {
+    eprintln!("chip8-headless: {}", message);
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn next_value(args: &mut dyn Iterator<Item = String>, flag: &str) -> String {
+    match args.next() {
+        Some(value) => value,
+        None => fail(&format!("{} needs a value", flag)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> T {
+    match value.parse() {
+        Ok(number) => number,
+        Err(_) => fail(&format!("{} expects a number, got '{}'", flag, value)),
+    }
+}
+
+fn write_output(path: &str, contents: &[u8]) {
+    let result = if path == "-" {
+        use std::io::Write;
+        std::io::stdout().write_all(contents)
+    } else {
+        std::fs::write(path, contents)
+    };
+    if let Err(e) = result {
+        eprintln!("chip8-headless: failed to write {}: {}", path, e);
+        process::exit(1);
+    }
+}
+
+fn main() {
+    let mut rom: Option<String> = None;
+    let mut conditions: Vec<StopCondition> = Vec::new();
+    let mut input: Option<String> = None;
+    let mut play_path: Option<String> = None;
+    let mut watchpoints: Vec<Watchpoint> = Vec::new();
+    let mut quirks: Quirks = Quirks::default();
+    let mut mode: Mode = Mode::Chip8;
+    let mut speed: Option<u32> = None;
+    let mut seed: u64 = 0;
+    let mut rng: RandomKind = RandomKind::Xorshift;
+    let mut text_path: Option<String> = None;
+    let mut png_path: Option<String> = None;
+    let mut png_scale: usize = 4;
+    let mut json_path: Option<String> = None;
+    let mut trace_path: Option<String> = None;
+    let mut trace_format: TraceFormat = TraceFormat::Text;
+    let mut trace_filter: TraceFilter = TraceFilter::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--frames" => conditions.push(StopCondition::Frames(parse_number(&next_value(&mut args, &arg), &arg))),
+            "--until-loop" => conditions.push(StopCondition::PcLoop),
+            "--until-opcode" => {
+                let value: String = next_value(&mut args, &arg);
+                match u16::from_str_radix(value.trim_start_matches("0x"), 16) {
+                    Ok(opcode) => conditions.push(StopCondition::Opcode(opcode)),
+                    Err(_) => fail(&format!("--until-opcode expects a hex opcode, got '{}'", value)),
+                }
+            }
+            "--timeout" => {
+                let seconds: f64 = parse_number(&next_value(&mut args, &arg), &arg);
+                match Duration::try_from_secs_f64(seconds) {
+                    Ok(timeout) if !timeout.is_zero() => conditions.push(StopCondition::Timeout(timeout)),
+                    _ => fail("--timeout must be a positive number of seconds"),
+                }
+            }
+            "--input" => input = Some(next_value(&mut args, &arg)),
+            "--play" => play_path = Some(next_value(&mut args, &arg)),
+            "--watch" => {
+                // logging is the useful default here, there is no one to resume a paused run
+                let spec: String = next_value(&mut args, &arg);
+                let mut watchpoint: Watchpoint = Watchpoint::parse(&spec).unwrap_or_else(|e| fail(&e));
+                if !spec.split(|c: char| c == ':' || c.is_whitespace()).any(|option| option == "break") {
+                    watchpoint.action = WatchAction::Log;
+                }
+                watchpoints.push(watchpoint);
+            }
+            "--quirks" => {
+                let name: String = next_value(&mut args, &arg);
+                quirks = Quirks::from_name(&name).unwrap_or_else(|| fail(&format!("unknown quirks profile '{}'", name)));
+            }
+            "--mode" => {
+                let name: String = next_value(&mut args, &arg);
+                mode = Mode::from_name(&name).unwrap_or_else(|| fail(&format!("unknown mode '{}'", name)));
+            }
+            "--speed" => {
+                speed = Some(parse_number(&next_value(&mut args, &arg), &arg));
+                if speed == Some(0) {
+                    fail("--speed must be at least 1");
+                }
+            }
+            "--seed" => seed = parse_number(&next_value(&mut args, &arg), &arg),
+            "--rng" => {
+                let name: String = next_value(&mut args, &arg);
+                rng = RandomKind::from_name(&name).unwrap_or_else(|| fail(&format!("unknown random generator '{}'", name)));
+            }
+            "--text" => text_path = Some(next_value(&mut args, &arg)),
+            "--png" => png_path = Some(next_value(&mut args, &arg)),
+            "--png-scale" => {
+                png_scale = parse_number(&next_value(&mut args, &arg), &arg);
+                if png_scale == 0 {
+                    fail("--png-scale must be at least 1");
+                }
+            }
+            "--json" => json_path = Some(next_value(&mut args, &arg)),
+            "--trace" => trace_path = Some(next_value(&mut args, &arg)),
+            "--trace-format" => {
+                let name: String = next_value(&mut args, &arg);
+                trace_format = TraceFormat::from_name(&name).unwrap_or_else(|| fail(&format!("unknown trace format '{}'", name)));
+            }
+            "--trace-pc" => {
+                trace_filter.addresses = Some(TraceFilter::parse_addresses(&next_value(&mut args, &arg)).unwrap_or_else(|e| fail(&e)));
+            }
+            "--trace-ops" => {
+                trace_filter.classes = Some(TraceFilter::parse_classes(&next_value(&mut args, &arg)).unwrap_or_else(|e| fail(&e)));
+            }
+            "--trace-frames" => {
+                trace_filter.frames = Some(TraceFilter::parse_frames(&next_value(&mut args, &arg)).unwrap_or_else(|e| fail(&e)));
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with("--") => fail(&format!("unknown option '{}'", arg)),
+            _ if rom.is_none() => rom = Some(arg),
+            _ => fail(&format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    let rom: String = rom.unwrap_or_else(|| fail("no rom given"));
+    if input.is_some() && play_path.is_some() {
+        fail("--input and --play can't be used together");
+    }
+    let movie: Option<Movie> = play_path.map(|path| {
+        let bytes: Vec<u8> = std::fs::read(&path).unwrap_or_else(|e| fail(&format!("failed to read {}: {}", path, e)));
+        Movie::from_bytes(&bytes).unwrap_or_else(|e| fail(&format!("{}: {}", path, e)))
+    });
+
+    // make sure a run always ends
+    let bounded: bool = conditions.iter().any(|condition| matches!(condition, StopCondition::Frames(_) | StopCondition::Timeout(_)));
+    if !bounded {
+        let frames: u64 = movie.as_ref().map_or(600, |movie| movie.length());
+        conditions.push(StopCondition::Frames(frames));
+    }
+
+    let mut cpu = Chip8::new(quirks);
+    cpu.set_mode(mode);
+    if let Some(speed) = speed {
+        cpu.set_speed(speed);
+    }
+    cpu.seed_rng(seed);
+    cpu.set_random_kind(rng);
+    if let Some(movie) = &movie {
+        movie.configure(&mut cpu);
+    }
+    cpu.initialize();
+    if let Err(e) = cpu.load(rom) {
+        eprintln!("chip8-headless: {}", e);
+        process::exit(1);
+    }
+    if let Some(Err(e)) = movie.as_ref().map(|movie| movie.check_rom(&cpu)) {
+        eprintln!("chip8-headless: {}", e);
+        process::exit(1);
+    }
+
+    for watchpoint in watchpoints {
+        cpu.watchpoints_mut().add(watchpoint);
+    }
+    if let Some(path) = &trace_path {
+        match Tracer::create(path, trace_format, trace_filter) {
+            Ok(tracer) => cpu.set_tracer(Some(tracer)),
+            Err(e) => {
+                eprintln!("chip8-headless: failed to create {}: {}", path, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let mut runner = HeadlessRunner::new(cpu);
+    for condition in conditions {
+        runner.add_stop_condition(condition);
+    }
+    if let Some(path) = input {
+        let script: InputScript = match std::fs::read_to_string(&path) {
+            Ok(text) => InputScript::parse(&text).unwrap_or_else(|e| fail(&e)),
+            Err(e) => fail(&format!("failed to read {}: {}", path, e)),
+        };
+        runner.set_input(script);
+    }
+    if let Some(movie) = &movie {
+        runner.set_input(movie.input_script());
+    }
+
+    let reason: StopReason = runner.run();
+    for hit in runner.watch_hits() {
+        eprintln!("{}", hit);
+    }
+    eprintln!("chip8-headless: stopped after {} frames, {}", runner.cpu().get_frame_count(), reason);
+    if let Some(tracer) = runner.cpu_mut().take_tracer() {
+        let records: u64 = tracer.records();
+        match tracer.finish() {
+            Ok(()) => eprintln!("chip8-headless: traced {} instructions", records),
+            Err(e) => {
+                eprintln!("chip8-headless: failed to write the trace: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    if let Some(path) = text_path {
+        write_output(&path, runner.framebuffer_text().as_bytes());
+    }
+    if let Some(path) = png_path {
+        write_output(&path, &runner.framebuffer_png(png_scale));
+    }
+    if let Some(path) = json_path {
+        write_output(&path, runner.registers_json(&reason).as_bytes());
+    }
+
+    if let StopReason::Error(_) = reason {
+        process::exit(1);
+    }
+}
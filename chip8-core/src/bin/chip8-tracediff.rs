@@ -0,0 +1,80 @@
+use std::process;
+
+use chip8_core::trace_diff::{diff, load, report, DiffOptions, TraceEntry};
+
+const USAGE: &str = "usage: chip8-tracediff <ours> <reference> [options]
+
+  --context N         instructions shown around the divergence (default 5)
+  --ignore-frames     don't compare frame numbers
+  --ignore-memory     don't compare memory writes
+
+Traces are text or binary as written by --trace, or text in the reference
+format described in chip8-core/src/trace_diff.rs. Exits with 0 when they
+match, 1 when they diverge and 2 on errors.";
+
+fn fail(message: &str) -> ! {
+    eprintln!("chip8-tracediff: {}", message);
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn next_value(args: &mut dyn Iterator<Item = String>, flag: &str) -> String {
+    match args.next() {
+        Some(value) => value,
+        None => fail(&format!("{} needs a value", flag)),
+    }
+}
+
+fn read_trace(path: &str) -> Vec<TraceEntry> {
+    let bytes: Vec<u8> = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("chip8-tracediff: failed to read {}: {}", path, e);
+            process::exit(2);
+        }
+    };
+    match load(&bytes) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("chip8-tracediff: {}: {}", path, e);
+            process::exit(2);
+        }
+    }
+}
+
+fn main() {
+    let mut paths: Vec<String> = Vec::new();
+    let mut options: DiffOptions = DiffOptions::default();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--context" => {
+                let value: String = next_value(&mut args, &arg);
+                options.context = value.parse().unwrap_or_else(|_| fail(&format!("bad context '{}'", value)));
+            }
+            "--ignore-frames" => options.compare_frames = false,
+            "--ignore-memory" => options.compare_memory = false,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with("--") => fail(&format!("unknown option '{}'", arg)),
+            _ if paths.len() < 2 => paths.push(arg),
+            _ => fail(&format!("unexpected argument '{}'", arg)),
+        }
+    }
+    if paths.len() < 2 {
+        fail("two traces are needed");
+    }
+
+    let ours: Vec<TraceEntry> = read_trace(&paths[0]);
+    let reference: Vec<TraceEntry> = read_trace(&paths[1]);
+    match diff(&ours, &reference, &options) {
+        Some(divergence) => {
+            print!("{}", report(&ours, &reference, &divergence, options.context));
+            process::exit(1);
+        }
+        None => println!("traces match ({} instructions)", ours.len()),
+    }
+}
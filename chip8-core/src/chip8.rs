@@ -0,0 +1,1380 @@
+
+
+extern crate rand;
+
+use std::vec::Vec;
+
+use crate::audio::Pattern;
+use crate::error::Chip8Error;
+use crate::instruction::{decode, Instruction};
+use crate::savestate::{self, StateReader, StateWriter};
+use crate::quirks::{IndexIncrement, Quirks};
+use crate::random::{RandomKind, RandomSource};
+use crate::trace::{Tracer, TRACED_REGISTERS};
+use crate::watch::{Access, Watchpoints};
+
+const MEMORY_SIZE: usize = 0x10000;
+const VREGISTER_COUNT: usize = 16;
+const LORES_WIDTH: usize = 64;
+const LORES_HEIGHT: usize = 32;
+const HIRES_WIDTH: usize = 128;
+const HIRES_HEIGHT: usize = 64;
+const GFX_SIZE: usize = HIRES_WIDTH * HIRES_HEIGHT;
+const STACK_SIZE: usize = 16;
+pub const KEY_SIZE: usize = 16;
+const RPL_FLAG_COUNT: usize = 16;
+const AUDIO_PATTERN_SIZE: usize = 16;
+const PROGRAM_START: usize = 0x200;
+const TIMER_HZ: u32 = 60;
+const DEFAULT_INSTRUCTIONS_PER_SECOND: u32 = 700;
+const FONT_ADDRESS: usize = 80;
+const BIG_FONT_ADDRESS: usize = FONT_ADDRESS + FONTSET_SIZE;
+
+/*
+    Instruction set and address space the cpu emulates. The SCHIP and XO-CHIP
+    instructions don't overlap with CHIP-8 ones so they are always decoded,
+    the mode decides how much memory a program can reach.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl Mode {
+    pub fn memory_size(&self) -> usize {
+        match self {
+            Mode::Chip8 | Mode::SuperChip => 0x1000,
+            Mode::XoChip => 0x10000,
+        }
+    }
+
+    // FX75/FX85 flag registers available
+    pub fn rpl_flag_count(&self) -> usize {
+        match self {
+            Mode::Chip8 | Mode::SuperChip => 8,
+            Mode::XoChip => 16,
+        }
+    }
+
+    pub(crate) fn to_id(self) -> u8 {
+        match self {
+            Mode::Chip8 => 0,
+            Mode::SuperChip => 1,
+            Mode::XoChip => 2,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Option<Mode> {
+        match id {
+            0 => Some(Mode::Chip8),
+            1 => Some(Mode::SuperChip),
+            2 => Some(Mode::XoChip),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Mode> {
+        match name.to_ascii_lowercase().as_str() {
+            "chip8" | "chip-8" => Some(Mode::Chip8),
+            "schip" | "superchip" | "super-chip" => Some(Mode::SuperChip),
+            "xochip" | "xo-chip" => Some(Mode::XoChip),
+            _ => None,
+        }
+    }
+}
+
+pub struct Chip8 {
+    opcode: u16,
+    memory: [u8;MEMORY_SIZE],
+    v: [u8;VREGISTER_COUNT],
+    i: u16,
+    pc: u16,
+    gfx: [u8;GFX_SIZE],
+    hires: bool,
+    exited: bool,
+    draw_sema: bool,
+    awaited_key: Option<u8>,
+    delay_timer: u8,
+    sound_timer: u8,
+    stack: [u16;STACK_SIZE],
+    sp: u8,
+    key: [u8;KEY_SIZE],
+    rpl: [u8;RPL_FLAG_COUNT],
+    plane_mask: u8,
+    audio_pattern: [u8;AUDIO_PATTERN_SIZE],
+    pitch: u8,
+    mode: Mode,
+    quirks: Quirks,
+    instructions_per_second: u32,
+    cycle_remainder: u32,
+    frame_count: u64,
+    rom_hash: u64,
+    seed: u64,
+    random_kind: Option<RandomKind>,
+    rng: Box<dyn RandomSource>,
+    watchpoints: Watchpoints,
+    tracer: Option<Tracer>
+}
+
+
+impl Chip8 {
+
+    pub fn new(quirks: Quirks) -> Chip8 {
+        let mut chip8 = Chip8 {
+            opcode: 0x0000,
+            memory: [0;MEMORY_SIZE],
+            v: [0;VREGISTER_COUNT],
+            i: 0x0000,
+            pc: 0x0000,
+            gfx: [0;GFX_SIZE],
+            hires: false,
+            exited: false,
+            draw_sema: false,
+            awaited_key: None,
+            delay_timer: 0x00,
+            sound_timer: 0x00,
+            stack: [0;STACK_SIZE],
+            sp: 0,
+            key: [0;KEY_SIZE],
+            rpl: [0;RPL_FLAG_COUNT],
+            plane_mask: 1,
+            audio_pattern: [0;AUDIO_PATTERN_SIZE],
+            pitch: 64,
+            mode: Mode::Chip8,
+            quirks,
+            instructions_per_second: DEFAULT_INSTRUCTIONS_PER_SECOND,
+            cycle_remainder: 0,
+            frame_count: 0,
+            rom_hash: savestate::rom_hash(&[]),
+            seed: 0,
+            random_kind: Some(RandomKind::Xorshift),
+            rng: RandomKind::Xorshift.create(0),
+            watchpoints: Watchpoints::new(),
+            tracer: None,
+        };
+        // unpredictable unless seeded, like the original hardware
+        chip8.seed_rng(rand::random());
+        chip8
+    }
+
+    /* 
+        Initialize memory and register contents
+    */
+    pub fn initialize(&mut self) {
+        self.pc = 0x200;
+        self.opcode = 0x0000;
+        self.i = 0x0000;
+        self.sp = 0;
+        self.hires = false;
+        self.exited = false;
+        self.awaited_key = None;
+        self.plane_mask = 1;
+        self.audio_pattern = [0;AUDIO_PATTERN_SIZE];
+        self.pitch = 64;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.cycle_remainder = 0;
+        self.frame_count = 0;
+
+        // Clear display
+        for i in 0..GFX_SIZE {
+            self.gfx[i] = 0x00;
+        }
+
+        // Clear stack
+        for i in 0..STACK_SIZE {
+            self.stack[i] = 0x00;
+        }
+
+        // Clear registers V0-VF
+        for i in 0..VREGISTER_COUNT {
+            self.v[i] = 0x00;
+        }
+
+        // Clear key
+        for i in 0..KEY_SIZE {
+            self.key[i] = 0;
+        }
+
+        // Clear memory
+        for i in 0..MEMORY_SIZE {
+            self.memory[i] = 0x00;
+        }
+
+        // Load fontsets
+        self.memory[FONT_ADDRESS..(FONT_ADDRESS + FONTSET_SIZE)].copy_from_slice(&CHIP8_FONTSET);
+        self.memory[BIG_FONT_ADDRESS..(BIG_FONT_ADDRESS + BIG_FONTSET_SIZE)].copy_from_slice(&SCHIP_BIG_FONTSET);
+    }
+
+    /*
+        Load program for cpu to run
+    */
+    pub fn load(&mut self, path_to_program: String) -> Result<(), Chip8Error> {
+
+        // let path_to_program: String = String::from("src/programs/INVADERS");
+
+        let buffer: Vec<u8> = match std::fs::read(&path_to_program) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return Err(Chip8Error::Io { path: path_to_program, source: e });
+            }
+        };
+
+        self.load_rom(&buffer)
+    }
+
+    /*
+        Load a program that is already in memory, for frontends without a filesystem
+    */
+    pub fn load_rom(&mut self, buffer: &[u8]) -> Result<(), Chip8Error> {
+        let max_size: usize = self.mode.memory_size() - PROGRAM_START;
+        if buffer.len() > max_size {
+            return Err(Chip8Error::RomTooLarge { size: buffer.len(), max: max_size });
+        }
+
+        self.memory[PROGRAM_START..(PROGRAM_START + buffer.len())].copy_from_slice(buffer);
+        self.rom_hash = savestate::rom_hash(buffer);
+        Ok(())
+    }
+
+    /*
+        Snapshot of the whole machine, see savestate for the format. Configuration
+        (quirks, speed, which random generator) is not part of the state, but the
+        generator's position is so CXNN continues identically after a restore.
+    */
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.bytes(&savestate::MAGIC);
+        writer.u16(savestate::VERSION);
+        writer.u64(self.rom_hash);
+        writer.u8(self.mode.to_id());
+
+        writer.u16(self.opcode);
+        writer.u16(self.pc);
+        writer.u16(self.i);
+        writer.u8(self.sp);
+        writer.bytes(&self.v);
+        for address in self.stack.iter() {
+            writer.u16(*address);
+        }
+        writer.u8(self.delay_timer);
+        writer.u8(self.sound_timer);
+        writer.bool(self.hires);
+        writer.bool(self.exited);
+        writer.u8(self.awaited_key.unwrap_or(0xFF));
+        writer.u8(self.plane_mask);
+        writer.u8(self.pitch);
+        writer.bytes(&self.audio_pattern);
+        writer.bytes(&self.rpl);
+        writer.bytes(&self.key);
+        writer.u32(self.cycle_remainder);
+        writer.u64(self.frame_count);
+        writer.u64(self.rng.state());
+        writer.bytes(&self.gfx);
+        writer.bytes(&self.memory[..self.mode.memory_size()]);
+        writer.finish()
+    }
+
+    /*
+        Restore a snapshot from save_state. The state must come from the same rom
+        and mode; on error the machine is left untouched.
+    */
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        let mut reader = StateReader::new(data);
+        if reader.bytes(4)? != savestate::MAGIC {
+            return Err(savestate::bad_state("not a save state"));
+        }
+        if reader.u16()? != savestate::VERSION {
+            return Err(savestate::bad_state("unsupported save state version"));
+        }
+        if reader.u64()? != self.rom_hash {
+            return Err(savestate::bad_state("saved from a different rom"));
+        }
+        if Mode::from_id(reader.u8()?) != Some(self.mode) {
+            return Err(savestate::bad_state("saved in a different mode"));
+        }
+
+        // check the size up front so a truncated state can't leave the machine half restored
+        let memory_size: usize = self.mode.memory_size();
+        let body_size: usize = 2 + 2 + 2 + 1 + VREGISTER_COUNT + STACK_SIZE * 2 + 1 + 1 + 1 + 1 + 1 + 1 + 1
+            + AUDIO_PATTERN_SIZE + RPL_FLAG_COUNT + KEY_SIZE + 4 + 8 + 8 + GFX_SIZE + memory_size;
+        if data.len() != savestate::HEADER_SIZE + body_size {
+            return Err(savestate::bad_state("wrong size for this mode"));
+        }
+
+        let opcode: u16 = reader.u16()?;
+        let pc: u16 = reader.u16()?;
+        let i: u16 = reader.u16()?;
+        let sp: u8 = reader.u8()?;
+        if sp as usize > STACK_SIZE {
+            return Err(savestate::bad_state("stack pointer out of range"));
+        }
+
+        self.opcode = opcode;
+        self.pc = pc;
+        self.i = i;
+        self.sp = sp;
+        self.v.copy_from_slice(reader.bytes(VREGISTER_COUNT)?);
+        for index in 0..STACK_SIZE {
+            self.stack[index] = reader.u16()?;
+        }
+        self.delay_timer = reader.u8()?;
+        self.sound_timer = reader.u8()?;
+        self.hires = reader.bool()?;
+        self.exited = reader.bool()?;
+        self.awaited_key = match reader.u8()? {
+            0xFF => None,
+            key => Some(key & 0x0F),
+        };
+        self.plane_mask = reader.u8()?;
+        self.pitch = reader.u8()?;
+        self.audio_pattern.copy_from_slice(reader.bytes(AUDIO_PATTERN_SIZE)?);
+        self.rpl.copy_from_slice(reader.bytes(RPL_FLAG_COUNT)?);
+        self.key.copy_from_slice(reader.bytes(KEY_SIZE)?);
+        self.cycle_remainder = reader.u32()?;
+        self.frame_count = reader.u64()?;
+        self.rng.set_state(reader.u64()?);
+        self.gfx.copy_from_slice(reader.bytes(GFX_SIZE)?);
+        self.memory[..memory_size].copy_from_slice(reader.bytes(memory_size)?);
+
+        // whatever is on screen belongs to the old state
+        self.draw_sema = true;
+        Ok(())
+    }
+
+    // hash of the loaded rom, identifies which program a state or recording belongs to
+    pub fn get_rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    /*
+        Emulate cpu cycle by fetching, decoding, executing opcode
+    */
+    pub fn emulate_cycle(&mut self) -> Result<(), Chip8Error> {
+        
+        //to slow down cycles for now
+        // std::thread::sleep(std::time::Duration::from_millis(40));
+
+        // 00FD halts the program for good, there is nothing left to run
+        if self.exited {
+            return Ok(());
+        }
+
+        // fetch opcode by combining two consecutive addresses in memory, the
+        // pc wraps at 16 bits so XO-CHIP code running off the end carries on at 0
+        self.opcode = (self.load_byte(self.pc as usize, Access::Fetch)? as u16) << 8 | (self.load_byte(self.pc.wrapping_add(1) as usize, Access::Fetch)? as u16);
+
+        let traced: bool = self.tracer.as_ref().is_some_and(|tracer| tracer.wants(self.frame_count, self.pc, self.opcode));
+        if traced {
+            let operand: u16 = match self.opcode {
+                0xF000 => self.peek_word(self.pc.wrapping_add(2) as usize),
+                _ => 0,
+            };
+            let registers: [u16;20] = self.traced_registers();
+            if let Some(tracer) = &mut self.tracer {
+                tracer.begin(self.frame_count, self.pc, self.opcode, operand, registers);
+            }
+        }
+
+        let result: Result<(), Chip8Error> = match decode(self.opcode) {
+            Some(instruction) => self.execute(instruction),
+            None => Err(self.unknown_opcode()),
+        };
+
+        if traced {
+            let registers: [u16;20] = self.traced_registers();
+            if let Some(tracer) = &mut self.tracer {
+                match result {
+                    Ok(()) => tracer.end(registers),
+                    Err(_) => tracer.cancel(),
+                }
+            }
+        }
+        result
+    }
+
+    /*
+        Carry out a decoded instruction, self.opcode still holds its opcode
+    */
+    fn execute(&mut self, instruction: Instruction) -> Result<(), Chip8Error> {
+        match instruction {
+
+            Instruction::Sys { .. } => {
+                //0NNN
+                //Calls RCA 1802 program at address NNN. Not necessary for most ROMs.
+                return Err(self.unknown_opcode());
+            }
+
+            Instruction::Clear => {
+                //00E0
+                //Clears the screen.
+                self.clear_screen();
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Return => {
+                //00EE
+                //Returns from a subroutine.
+                if self.sp == 0 {
+                    return Err(Chip8Error::StackUnderflow { pc: self.pc });
+                }
+                self.sp -= 1;
+                self.pc = self.stack[self.sp as usize].wrapping_add(2);
+            }
+
+            Instruction::ScrollDown { n } => {
+                //00CN (SCHIP)
+                //Scrolls the display down by N pixels.
+                self.scroll(0, n as isize);
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::ScrollRight => {
+                //00FB (SCHIP)
+                //Scrolls the display right by 4 pixels.
+                self.scroll(4, 0);
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::ScrollLeft => {
+                //00FC (SCHIP)
+                //Scrolls the display left by 4 pixels.
+                self.scroll(-4, 0);
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Exit => {
+                //00FD (SCHIP)
+                //Exits the interpreter.
+                self.exited = true;
+            }
+
+            Instruction::Lores => {
+                //00FE (SCHIP)
+                //Switches to 64x32 low resolution mode.
+                self.hires = false;
+                self.clear_all_planes();
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Hires => {
+                //00FF (SCHIP)
+                //Switches to 128x64 high resolution mode.
+                self.hires = true;
+                self.clear_all_planes();
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Jump { nnn } => {
+                //1NNN
+                //Jumps to address NNN.
+                self.pc = nnn;
+            }
+
+            Instruction::Call { nnn } => {
+                //2NNN
+                //Calls subroutine at NNN.
+                if self.sp as usize >= STACK_SIZE {
+                    return Err(Chip8Error::StackOverflow { pc: self.pc });
+                }
+                self.stack[self.sp as usize] = self.pc;
+                self.sp += 1;
+                self.pc = nnn;
+            }
+
+            Instruction::SkipIfEqual { x, nn } => {
+                //3XNN
+                //Skips the next instruction if VX equals NN.
+                if self.v[x as usize] == nn {
+                    self.skip_next_instruction();
+                } else {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+
+            Instruction::SkipIfNotEqual { x, nn } => {
+                //4XNN
+                //Skips the next instruction if VX doesn't equal NN.
+                if self.v[x as usize] != nn {
+                    self.skip_next_instruction();
+                } else {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+
+            Instruction::SkipIfRegistersEqual { x, y } => {
+                //5XY0
+                //Skips the next instruction if VX equals VY.
+                if self.v[x as usize] == self.v[y as usize] {
+                    self.skip_next_instruction();
+                } else {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+
+            Instruction::SaveRange { x, y } => {
+                //5XY2 (XO-CHIP)
+                //Stores VX to VY (in either order) in memory starting at address I. I is left unmodified.
+                for (offset, reg_index) in register_range(x as usize, y as usize).enumerate() {
+                    self.store_byte((self.i as usize) + offset, self.v[reg_index])?;
+                }
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::LoadRange { x, y } => {
+                //5XY3 (XO-CHIP)
+                //Fills VX to VY (in either order) from memory starting at address I. I is left unmodified.
+                for (offset, reg_index) in register_range(x as usize, y as usize).enumerate() {
+                    self.v[reg_index] = self.load_byte((self.i as usize) + offset, Access::Read)?;
+                }
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Set { x, nn } => {
+                //6XNN
+                //Sets VX to NN.
+                self.v[x as usize] = nn;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::AddImmediate { x, nn } => {
+                //7XNN
+                //Adds NN to VX.
+                let x: usize = x as usize;
+                self.v[x] = self.v[x].wrapping_add(nn);
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Copy { x, y } => {
+                //8XY0
+                //Sets VX to the value of VY.
+                self.v[x as usize] = self.v[y as usize];
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Or { x, y } => {
+                //8XY1
+                //Sets VX to VX or VY
+                self.v[x as usize] |= self.v[y as usize];
+                if self.quirks.logic_resets_vf {
+                    self.v[VREGISTER_COUNT - 1] = 0;
+                }
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::And { x, y } => {
+                //8XY2
+                //Sets VX to VX and VY.
+                self.v[x as usize] &= self.v[y as usize];
+                if self.quirks.logic_resets_vf {
+                    self.v[VREGISTER_COUNT - 1] = 0;
+                }
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Xor { x, y } => {
+                //8XY3
+                //Sets VX to VX xor VY.
+                self.v[x as usize] ^= self.v[y as usize];
+                if self.quirks.logic_resets_vf {
+                    self.v[VREGISTER_COUNT - 1] = 0;
+                }
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Add { x, y } => {
+                //8XY4
+                //Adds VY to VX. VF is set to 1 when there's a carry, and to 0 when there isn't.
+                //VF is written last so the flag wins when X is F.
+                let (sum, carry) = self.v[x as usize].overflowing_add(self.v[y as usize]);
+                self.v[x as usize] = sum;
+                self.v[VREGISTER_COUNT - 1] = carry as u8;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Subtract { x, y } => {
+                //8XY5
+                //VY is subtraced from VX. VF is set to 0 when there's a borrow, and 1 when there isn't.
+                let (difference, borrow) = self.v[x as usize].overflowing_sub(self.v[y as usize]);
+                self.v[x as usize] = difference;
+                self.v[VREGISTER_COUNT - 1] = !borrow as u8;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::ShiftRight { x, y } => {
+                //8XY6
+                //Stores the least significant bit of VX in VF and then shifts VX to the right by 1.
+                //With the shift quirk VY is shifted into VX instead.
+                let source: u8 = if self.quirks.shift_uses_vy { self.v[y as usize] } else { self.v[x as usize] };
+                self.v[x as usize] = source >> 1;
+                self.v[VREGISTER_COUNT - 1] = source & 0x01;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::SubtractReversed { x, y } => {
+                //8XY7
+                //Sets VX to VY minus VX. VF is set to 0 when there's a borrow, and 1 when there isn't.
+                let (difference, borrow) = self.v[y as usize].overflowing_sub(self.v[x as usize]);
+                self.v[x as usize] = difference;
+                self.v[VREGISTER_COUNT - 1] = !borrow as u8;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::ShiftLeft { x, y } => {
+                //8XYE
+                //Stores the most significant bit of VX in VF and then shifts VX to the left by 1.
+                //With the shift quirk VY is shifted into VX instead.
+                let source: u8 = if self.quirks.shift_uses_vy { self.v[y as usize] } else { self.v[x as usize] };
+                self.v[x as usize] = source << 1;
+                self.v[VREGISTER_COUNT - 1] = (source & 0x80) >> 7;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::SkipIfRegistersNotEqual { x, y } => {
+                //9XY0
+                //Skips the next instruction if VX doesn't equal VY.
+                if self.v[x as usize] != self.v[y as usize] {
+                    self.skip_next_instruction();
+                } else {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+
+            Instruction::SetIndex { nnn } => {
+                //ANNN
+                //Sets I to the addresss NNN
+                self.i = nnn;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::JumpOffset { nnn } => {
+                //BNNN
+                //Jumps to the address NNN plus V0 (or XNN plus VX with the jump quirk)
+                let offset_reg: usize = if self.quirks.jump_uses_vx { (nnn >> 8) as usize } else { 0 };
+                self.pc = (self.v[offset_reg] as u16) + nnn;
+            }
+
+            Instruction::Random { x, nn } => {
+                //CXNN
+                //Sets VX to the result of a bitwise and operation on a random number (Typically: 0 to 255) and NN.
+                let random_num: u8 = self.rng.next_byte();
+                self.v[x as usize] = nn & random_num;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Draw { x, y, n } => {
+                //DXYN
+                //Draws a sprite at coordinate (VX, VY) that has a width of 8 pixels and a height of N pixels. Each row of 8 pixels is read as
+                //bit-coded starting from memory location I; I value doesn't change after the execution of this instruction. As described
+                //above, VF is set to 1 if any screen pixels are flipped from set to unset when the sprite is drawn,
+                //and to 0 if that doesn't happen
+                //DXY0 (SCHIP) draws a 16x16 sprite instead, read as 16 rows of two bytes. SCHIP 1.1 only does that
+                //in hires, in lores it draws 16 rows of one byte. The VIP draws nothing.
+                let (x, y): (usize, usize) = (self.v[x as usize] as usize, self.v[y as usize] as usize);
+                let (width, height): (usize, usize) = match n {
+                    0 if self.hires || self.mode == Mode::XoChip => (16, 16),
+                    0 if self.mode == Mode::SuperChip => (8, 16),
+                    0 => (8, 0),
+                    n => (8, n as usize),
+                };
+                self.draw_sprite(x, y, width, height)?;
+
+                self.draw_sema = true;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::SkipIfKey { x } => {
+                //EX9E
+                //Skips the next instruction if the key store in VX is pressed.
+                if self.key[(self.v[x as usize] & 0x0F) as usize] == 0 {
+                    self.pc = self.pc.wrapping_add(2);
+                } else {
+                    self.skip_next_instruction();
+                }
+            }
+
+            Instruction::SkipIfNotKey { x } => {
+                //EXA1
+                //Skips the next instruction if the key stored in VX isn't pressed.
+                if self.key[(self.v[x as usize] & 0x0F) as usize] == 0 {
+                    self.skip_next_instruction();
+                } else {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+
+            Instruction::SetIndexLong => {
+                //F000 NNNN (XO-CHIP)
+                //Sets I to the 16 bit address stored in the next two bytes.
+                let high: u8 = self.load_byte(self.pc.wrapping_add(2) as usize, Access::Fetch)?;
+                let low: u8 = self.load_byte(self.pc.wrapping_add(3) as usize, Access::Fetch)?;
+                self.i = (high as u16) << 8 | (low as u16);
+                self.pc = self.pc.wrapping_add(4);
+            }
+
+            Instruction::SelectPlanes { n } => {
+                //FN01 (XO-CHIP)
+                //Selects the bitplanes N affected by drawing, clearing and scrolling.
+                self.plane_mask = n;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::LoadAudio => {
+                //F002 (XO-CHIP)
+                //Loads the 16 byte audio pattern buffer from memory starting at address I.
+                for offset in 0..AUDIO_PATTERN_SIZE {
+                    self.audio_pattern[offset] = self.load_byte((self.i as usize) + offset, Access::Read)?;
+                }
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::GetDelay { x } => {
+                //FX07
+                //Sets VX to the value of the delay timer.
+                self.v[x as usize] = self.delay_timer;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::WaitKey { x } => {
+                //FX0A
+                //A key press is awaited, and then stored in VX. (Blocking Operation)
+                //Like the VIP the key only counts once it is released again. Until then the pc
+                //stays on this instruction so the cpu spins here while the timers keep running.
+                match self.awaited_key {
+                    None => {
+                        if let Some(pressed) = self.key.iter().position(|&state| state != 0) {
+                            self.awaited_key = Some(pressed as u8);
+                        }
+                    }
+                    Some(pressed) => {
+                        if self.key[pressed as usize] == 0 {
+                            self.v[x as usize] = pressed;
+                            self.awaited_key = None;
+                            self.pc = self.pc.wrapping_add(2);
+                        }
+                    }
+                }
+            }
+
+            Instruction::SetDelay { x } => {
+                //FX15
+                //Sets the delay timer to VX.
+                self.delay_timer = self.v[x as usize];
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::SetSound { x } => {
+                //FX18
+                //Sets the sound timer to VX.
+                self.sound_timer = self.v[x as usize];
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::AddIndex { x } => {
+                //FX1E
+                //Adds VX to I. VF is set to 1 when there is a range overflow, and to 0 when there isn't.
+                let value: u16 = self.v[x as usize] as u16;
+                match self.i.checked_add(value) {
+                    Some(_result) => {
+                        self.v[VREGISTER_COUNT - 1] = 0;
+                    }
+                    None => {
+                        self.v[VREGISTER_COUNT - 1] = 1;
+                    }
+                }
+                self.i = self.i.wrapping_add(value);
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::FontCharacter { x } => {
+                //FX29
+                //Sets I to the location of the sprite for the character in VX. Characters 0-F (in hexadecimal)
+                //are represented by a 4x5 font.
+                self.i = ((self.v[x as usize] & 0x0F) as u16) * 5 + FONT_ADDRESS as u16;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::BigFontCharacter { x } => {
+                //FX30 (SCHIP)
+                //Sets I to the location of the 8x10 sprite for the digit in VX.
+                self.i = ((self.v[x as usize] & 0x0F) as u16) * 10 + BIG_FONT_ADDRESS as u16;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Bcd { x } => {
+                //FX33
+                //Stores the binary-coded decimal representation of VX, with the most significant
+                //of three digits a the adress in I, the middle digit at I plus 1, and the least significant digit at I plus 2.
+                let value: u8 = self.v[x as usize];
+                let address: usize = self.i as usize;
+                self.store_byte(address, value / 100)?;
+                self.store_byte(address.wrapping_add(1), (value / 10) % 10)?;
+                self.store_byte(address.wrapping_add(2), (value % 100) % 10)?;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::SetPitch { x } => {
+                //FX3A (XO-CHIP)
+                //Sets the audio pattern playback pitch to VX.
+                self.pitch = self.v[x as usize];
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Store { x } => {
+                //FX55
+                //Stores V0 to VX (including VX) in memory starting at address I. The offset from I
+                //is increased by 1 for each value written, but I itself is left unmodified (depending on quirks).
+                let x: usize = x as usize;
+                for reg_index in 0..(x + 1) {
+                    self.store_byte((self.i as usize).wrapping_add(reg_index), self.v[reg_index])?;
+                }
+                self.increment_index_after_load_store(x);
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::Load { x } => {
+                //FX65
+                //Fills V0 to VX (including VX) with values from memory starting at address I. The offset from I
+                //is increased by 1 for each value written, but I itself is left unmodified (depending on quirks).
+                let x: usize = x as usize;
+                for reg_index in 0..(x + 1) {
+                    self.v[reg_index] = self.load_byte((self.i as usize).wrapping_add(reg_index), Access::Read)?;
+                }
+                self.increment_index_after_load_store(x);
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::SaveFlags { x } => {
+                //FX75 (SCHIP)
+                //Stores V0 to VX in the RPL user flags (X < 8, or 16 on XO-CHIP).
+                let x: usize = x as usize;
+                if x >= self.mode.rpl_flag_count() {
+                    return Err(self.unknown_opcode());
+                }
+                self.rpl[..=x].copy_from_slice(&self.v[..=x]);
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            Instruction::LoadFlags { x } => {
+                //FX85 (SCHIP)
+                //Fills V0 to VX from the RPL user flags (X < 8, or 16 on XO-CHIP).
+                let x: usize = x as usize;
+                if x >= self.mode.rpl_flag_count() {
+                    return Err(self.unknown_opcode());
+                }
+                self.v[..=x].copy_from_slice(&self.rpl[..=x]);
+                self.pc = self.pc.wrapping_add(2);
+            }
+        }
+
+        Ok(())
+    }
+
+    /*
+        Run one 60 Hz frame: the share of instructions per second that falls in this
+        frame followed by a single timer tick. The remainder of the division carries
+        over so e.g. 700 instructions per second really runs 700 a second.
+    */
+    pub fn run_frame(&mut self) -> Result<(), Chip8Error> {
+        let cycles: u32 = self.start_frame();
+
+        for _ in 0..cycles {
+            if self.exited {
+                break;
+            }
+            self.emulate_cycle()?;
+        }
+
+        self.end_frame();
+        Ok(())
+    }
+
+    /*
+        Number of instructions to run in the next frame, for callers that step
+        through a frame themselves instead of using run_frame
+    */
+    pub fn start_frame(&mut self) -> u32 {
+        self.cycle_remainder += self.instructions_per_second;
+        let cycles: u32 = self.cycle_remainder / TIMER_HZ;
+        self.cycle_remainder %= TIMER_HZ;
+        cycles
+    }
+
+    pub fn end_frame(&mut self) {
+        self.tick_timers();
+        self.frame_count += 1;
+    }
+
+    /*
+        Decrement the delay and sound timers, called at 60 Hz
+    */
+    pub fn tick_timers(&mut self) {
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+    }
+
+    /*
+        Make CXNN repeatable by seeding its random number generator
+    */
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng.set_state(seed);
+    }
+
+    // the seed last given to seed_rng (or picked at random by new)
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /*
+        Swap in a built in generator, seeded with the current seed
+    */
+    pub fn set_random_kind(&mut self, kind: RandomKind) {
+        self.random_kind = Some(kind);
+        self.rng = kind.create(self.seed);
+    }
+
+    // None once a custom source has been swapped in
+    pub fn get_random_kind(&self) -> Option<RandomKind> {
+        self.random_kind
+    }
+
+    /*
+        Swap in any generator, e.g. one replaying recorded values in a test
+    */
+    pub fn set_random_source(&mut self, source: Box<dyn RandomSource>) {
+        self.random_kind = None;
+        self.rng = source;
+    }
+
+    pub fn set_speed(&mut self, instructions_per_second: u32) {
+        self.instructions_per_second = instructions_per_second;
+    }
+
+    pub fn get_speed(&self) -> u32 {
+        self.instructions_per_second
+    }
+
+    // frames run since initialize
+    pub fn get_frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /*
+        Bounds checked memory access. Addresses come from I or PC, which a
+        misbehaving program can point anywhere.
+    */
+    fn read_memory(&self, address: usize) -> Result<u8, Chip8Error> {
+        match self.memory[..self.mode.memory_size()].get(address) {
+            Some(value) => Ok(*value),
+            None => Err(Chip8Error::MemoryOutOfBounds { address, pc: self.pc }),
+        }
+    }
+
+    fn write_memory(&mut self, address: usize, value: u8) -> Result<(), Chip8Error> {
+        let memory_size: usize = self.mode.memory_size();
+        match self.memory[..memory_size].get_mut(address) {
+            Some(cell) => {
+                *cell = value;
+                Ok(())
+            }
+            None => Err(Chip8Error::MemoryOutOfBounds { address, pc: self.pc }),
+        }
+    }
+
+    /*
+        Memory accesses made by the program itself, reported to the watchpoints
+    */
+    fn load_byte(&mut self, address: usize, access: Access) -> Result<u8, Chip8Error> {
+        let value: u8 = self.read_memory(address)?;
+        self.watchpoints.check(self.pc, address, access, value, self.frame_count);
+        Ok(value)
+    }
+
+    fn store_byte(&mut self, address: usize, value: u8) -> Result<(), Chip8Error> {
+        self.write_memory(address, value)?;
+        self.watchpoints.check(self.pc, address, Access::Write, value, self.frame_count);
+        if let Some(tracer) = &mut self.tracer {
+            tracer.note_write(address, value);
+        }
+        Ok(())
+    }
+
+    // a word of memory without reporting the access, 0 past the end
+    fn peek_word(&self, address: usize) -> u16 {
+        match (self.read_memory(address), self.read_memory(address + 1)) {
+            (Ok(high), Ok(low)) => (high as u16) << 8 | low as u16,
+            _ => 0,
+        }
+    }
+
+    fn traced_registers(&self) -> [u16;20] {
+        TRACED_REGISTERS.map(|register| register.read(self))
+    }
+
+    /*
+        Skip over the next instruction, which is four bytes long if it is an XO-CHIP F000 NNNN
+    */
+    fn skip_next_instruction(&mut self) {
+        let next_opcode: u16 = self.peek_word(self.pc.wrapping_add(2) as usize);
+        let length: usize = decode(next_opcode).map_or(2, |instruction| instruction.length());
+        self.pc = self.pc.wrapping_add(2 + length as u16);
+    }
+
+    /*
+        Clear the selected bitplanes, which is the whole screen outside XO-CHIP programs
+    */
+    fn clear_screen(&mut self) {
+        for gfx_index in 0..GFX_SIZE {
+            self.gfx[gfx_index] &= !self.plane_mask;
+        }
+        self.draw_sema = true;
+    }
+
+    fn clear_all_planes(&mut self) {
+        for gfx_index in 0..GFX_SIZE {
+            self.gfx[gfx_index] = 0;
+        }
+        self.draw_sema = true;
+    }
+
+    /*
+        XOR a sprite of the given size from memory at I onto the screen, setting VF on collision.
+        Sprites wider than 8 pixels are stored as consecutive bytes per row. With several XO-CHIP
+        bitplanes selected the sprite data for each plane follows the previous one in memory.
+    */
+    fn draw_sprite(&mut self, vx: usize, vy: usize, width: usize, height: usize) -> Result<(), Chip8Error> {
+        let (screen_width, screen_height) = self.get_resolution();
+        let bytes_per_row: usize = width / 8;
+        let sprite_size: usize = bytes_per_row * height;
+
+        // the starting position always wraps, the quirk decides whether the rest of the sprite does
+        let start_x: usize = vx % screen_width;
+        let start_y: usize = vy % screen_height;
+
+        self.v[VREGISTER_COUNT - 1] = 0;
+        let mut sprite_address: usize = self.i as usize;
+        for plane in 0..4 {
+            let plane_bit: u8 = 1 << plane;
+            if self.plane_mask & plane_bit == 0 {
+                continue;
+            }
+            for y_coord in 0..height {
+                if !self.quirks.wrap_sprites && start_y + y_coord >= screen_height {
+                    break;
+                }
+                for byte_index in 0..bytes_per_row {
+                    let pixel: u8 = self.load_byte(sprite_address + y_coord * bytes_per_row + byte_index, Access::Read)?;
+                    for bit in 0..8 {
+                        let x_coord: usize = byte_index * 8 + bit;
+                        if !self.quirks.wrap_sprites && start_x + x_coord >= screen_width {
+                            break;
+                        }
+                        let gfx_index: usize = (start_x + x_coord) % screen_width + ((start_y + y_coord) % screen_height) * screen_width;
+                        if (pixel & (0x80 >> bit)) != 0 {
+                            if self.gfx[gfx_index] & plane_bit != 0 {
+                                self.v[VREGISTER_COUNT - 1] = 1;
+                            }
+                            self.gfx[gfx_index] ^= plane_bit;
+                        }
+                    }
+                }
+            }
+            sprite_address += sprite_size;
+        }
+        Ok(())
+    }
+
+    /*
+        Shift the active display area by dx, dy pixels, filling the gap with blank pixels
+    */
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let (width, height) = self.get_resolution();
+        let old: [u8;GFX_SIZE] = self.gfx;
+        for y_coord in 0..height {
+            for x_coord in 0..width {
+                let src_x: isize = x_coord as isize - dx;
+                let src_y: isize = y_coord as isize - dy;
+                let inside: bool = src_x >= 0 && src_y >= 0 && (src_x as usize) < width && (src_y as usize) < height;
+                let shifted: u8 = if inside { old[src_x as usize + src_y as usize * width] } else { 0 };
+                // only the selected bitplanes move
+                self.gfx[x_coord + y_coord * width] = (shifted & self.plane_mask) | (old[x_coord + y_coord * width] & !self.plane_mask);
+            }
+        }
+        self.draw_sema = true;
+    }
+
+    fn increment_index_after_load_store(&mut self, x: usize) {
+        match self.quirks.load_store_increment {
+            IndexIncrement::None => {}
+            IndexIncrement::X => self.i = self.i.wrapping_add(x as u16),
+            IndexIncrement::XPlusOne => self.i = self.i.wrapping_add((x + 1) as u16),
+        }
+    }
+
+    fn unknown_opcode(&self) -> Chip8Error {
+        Chip8Error::UnknownOpcode { opcode: self.opcode, pc: self.pc }
+    }
+
+    /*
+        Summary of the cpu registers, used to report state after an error
+    */
+    pub fn dump_registers(&self) -> String {
+        let mut out = format!("pc: {:#05x}  opcode: {:#06x}  i: {:#05x}  sp: {}  dt: {}  st: {}\n",
+            self.pc, self.opcode, self.i, self.sp, self.delay_timer, self.sound_timer);
+        for (index, value) in self.v.iter().enumerate() {
+            out.push_str(&format!("v{:X}: {:#04x} ", index, value));
+        }
+        out
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /*
+        Pixels of the active display, row major, get_resolution() wide
+    */
+    pub fn get_gfx(&self) -> &[u8] {
+        let (width, height) = self.get_resolution();
+        &self.gfx[..width * height]
+    }
+
+    // the address space of the current mode
+    pub fn get_memory(&self) -> &[u8] {
+        &self.memory[..self.mode.memory_size()]
+    }
+
+    pub fn get_pc(&self) -> u16 {
+        self.pc
+    }
+
+    // last opcode fetched
+    pub fn get_opcode(&self) -> u16 {
+        self.opcode
+    }
+
+    pub fn get_v(&self) -> [u8;VREGISTER_COUNT] {
+        self.v
+    }
+
+    pub fn get_i(&self) -> u16 {
+        self.i
+    }
+
+    pub fn get_sp(&self) -> u8 {
+        self.sp
+    }
+
+    pub fn get_stack(&self) -> [u16;STACK_SIZE] {
+        self.stack
+    }
+
+    pub fn get_delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    pub fn get_sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    /*
+        Setters for tools that poke at the machine (debuggers, test harnesses)
+    */
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+    }
+
+    pub fn set_v(&mut self, index: usize, value: u8) -> Result<(), Chip8Error> {
+        let register: &mut u8 = self.v.get_mut(index)
+            .ok_or_else(|| Chip8Error::BadRegister { reason: format!("there is no register v{:x}", index) })?;
+        *register = value;
+        Ok(())
+    }
+
+    pub fn set_i(&mut self, i: u16) {
+        self.i = i;
+    }
+
+    // at most the stack size, the stack entries below it are left as they are
+    pub fn set_sp(&mut self, sp: u8) -> Result<(), Chip8Error> {
+        if sp as usize > STACK_SIZE {
+            return Err(Chip8Error::BadRegister { reason: format!("stack pointer {} past the stack", sp) });
+        }
+        self.sp = sp;
+        Ok(())
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay_timer = value;
+    }
+
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.sound_timer = value;
+    }
+
+    pub fn set_memory(&mut self, address: usize, value: u8) -> Result<(), Chip8Error> {
+        self.write_memory(address, value)
+    }
+
+    /*
+        Trace the instructions run from now on, None turns tracing off
+    */
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    // hands the tracer back so it can be finished, tracing stops
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    pub fn watchpoints(&self) -> &Watchpoints {
+        &self.watchpoints
+    }
+
+    pub fn watchpoints_mut(&mut self) -> &mut Watchpoints {
+        &mut self.watchpoints
+    }
+
+    pub fn get_resolution(&self) -> (usize, usize) {
+        if self.hires {
+            (HIRES_WIDTH, HIRES_HEIGHT)
+        } else {
+            (LORES_WIDTH, LORES_HEIGHT)
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /*
+        Select the instruction set before initializing and loading a program
+    */
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    /*
+        XO-CHIP audio: the 128 bit pattern loaded by F002, one bit per sample
+    */
+    pub fn get_audio_pattern(&self) -> [u8;AUDIO_PATTERN_SIZE] {
+        self.audio_pattern
+    }
+
+    /*
+        Samples per second the audio pattern is played back at, set through FX3A
+    */
+    pub fn get_playback_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+
+    /*
+        What the buzzer plays in XO-CHIP mode once F002 has loaded a pattern,
+        None for the plain tone
+    */
+    pub fn get_sound_pattern(&self) -> Option<Pattern> {
+        if self.mode != Mode::XoChip || self.audio_pattern.iter().all(|&byte| byte == 0) {
+            return None;
+        }
+        Some(Pattern { bits: self.audio_pattern, rate: self.get_playback_rate() })
+    }
+
+    // the buzzer sounds while the sound timer is non-zero
+    pub fn is_sound_playing(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    pub fn update_keystate(&mut self, key: &[u8;KEY_SIZE]) {
+        self.key = *key;
+    }
+
+    // true while FX0A is blocking on a key press
+    pub fn is_waiting_for_key(&self) -> bool {
+        let high: Option<u8> = self.read_memory(self.pc as usize).ok();
+        let low: Option<u8> = self.read_memory(self.pc.wrapping_add(1) as usize).ok();
+        match (high, low) {
+            (Some(high), Some(low)) => high & 0xF0 == 0xF0 && low == 0x0A,
+            _ => false,
+        }
+    }
+
+    pub fn check_draw_sema(&mut self) -> bool {
+        if self.draw_sema {
+            self.draw_sema = false;
+            return true
+        }
+        false
+    }
+
+}
+
+
+/*
+    Registers X through Y for 5XY2/5XY3, counting down when X > Y
+*/
+fn register_range(x: usize, y: usize) -> Box<dyn Iterator<Item = usize>> {
+    if x <= y {
+        Box::new(x..=y)
+    } else {
+        Box::new((y..=x).rev())
+    }
+}
+
+const FONTSET_SIZE: usize = 80;
+const CHIP8_FONTSET: [u8;80] =
+[
+  0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+  0x20, 0x60, 0x20, 0x20, 0x70, // 1
+  0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+  0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+  0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+  0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+  0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+  0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+  0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+  0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+  0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+  0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+  0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+  0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+  0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+  0xF0, 0x80, 0xF0, 0x80, 0x80  // F
+];
+
+// SCHIP 8x10 digits for FX30, A-F included as most interpreters do
+const BIG_FONTSET_SIZE: usize = 160;
+const SCHIP_BIG_FONTSET: [u8;160] =
+[
+  0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+  0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+  0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+  0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+  0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+  0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+  0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+  0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+  0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+  0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+  0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+  0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+  0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+  0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0  // F
+];
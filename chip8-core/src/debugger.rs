@@ -0,0 +1,619 @@
+use std::fmt;
+
+use crate::chip8::Chip8;
+use crate::error::Chip8Error;
+use crate::instruction::{decode, Instruction};
+use crate::watch::Watchpoint;
+
+/*
+    Step debugger that drives the cpu in place of Chip8::run_frame. It keeps
+    its own position inside the current frame so execution can stop between
+    any two instructions and pick up from there, with timers still ticking
+    once per completed frame. Frontends feed it commands, parsed from text by
+    DebugCommand::parse, and show register_view while paused.
+*/
+
+pub const HELP: &str = "debugger commands:
+  c, continue             resume execution
+  p, pause                pause execution
+  s, step                 run one instruction
+  n, next                 run one instruction, stepping over 2NNN calls
+  o, out                  run until the current subroutine returns
+  b ADDR [if COND]        break when the pc reaches ADDR (hex), optionally only if COND holds
+  b if COND               break when COND becomes true, e.g. 'b if v3 == 5' or 'b if i >= 0x300'
+  d N                     delete breakpoint N
+  w ADDR[-END] [r|w|x] [log]
+                          watch memory reads, writes or opcode fetches (hex addresses),
+                          pausing after the instruction or just logging the accessing pc
+  wd N                    delete watchpoint N
+  l, list                 list breakpoints and watchpoints
+  r, regs                 show registers, timers and the stack
+  x ADDR [LEN]            dump LEN (default 16) bytes of memory from ADDR (hex)
+  h, help                 show this message
+conditions compare v0-vf, i, pc, sp, dt or st with ==, !=, <, <=, > or >= against a decimal or 0x hex value";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    V(u8),
+    I,
+    Pc,
+    Sp,
+    DelayTimer,
+    SoundTimer,
+}
+
+impl Register {
+    pub fn from_name(name: &str) -> Option<Register> {
+        let name: String = name.to_ascii_lowercase();
+        match name.as_str() {
+            "i" => Some(Register::I),
+            "pc" => Some(Register::Pc),
+            "sp" => Some(Register::Sp),
+            "dt" => Some(Register::DelayTimer),
+            "st" => Some(Register::SoundTimer),
+            _ if name.len() == 2 && name.starts_with('v') => {
+                u8::from_str_radix(&name[1..], 16).ok().map(Register::V)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn read(self, cpu: &Chip8) -> u16 {
+        match self {
+            Register::V(index) => cpu.get_v()[index as usize] as u16,
+            Register::I => cpu.get_i(),
+            Register::Pc => cpu.get_pc(),
+            Register::Sp => cpu.get_sp() as u16,
+            Register::DelayTimer => cpu.get_delay_timer() as u16,
+            Register::SoundTimer => cpu.get_sound_timer() as u16,
+        }
+    }
+
+    // 8 bit registers take the low byte of value, a stack pointer past the stack is refused
+    pub fn write(self, cpu: &mut Chip8, value: u16) -> Result<(), Chip8Error> {
+        match self {
+            Register::V(index) => return cpu.set_v(index as usize, value as u8),
+            Register::I => cpu.set_i(value),
+            Register::Pc => cpu.set_pc(value),
+            Register::Sp => return cpu.set_sp(value.min(u8::MAX as u16) as u8),
+            Register::DelayTimer => cpu.set_delay_timer(value as u8),
+            Register::SoundTimer => cpu.set_sound_timer(value as u8),
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Register::V(index) => write!(f, "v{:x}", index),
+            Register::I => write!(f, "i"),
+            Register::Pc => write!(f, "pc"),
+            Register::Sp => write!(f, "sp"),
+            Register::DelayTimer => write!(f, "dt"),
+            Register::SoundTimer => write!(f, "st"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    pub fn from_symbol(symbol: &str) -> Option<Comparison> {
+        match symbol {
+            "==" | "=" => Some(Comparison::Equal),
+            "!=" => Some(Comparison::NotEqual),
+            "<" => Some(Comparison::Less),
+            "<=" => Some(Comparison::LessOrEqual),
+            ">" => Some(Comparison::Greater),
+            ">=" => Some(Comparison::GreaterOrEqual),
+            _ => None,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+
+    fn holds(self, left: u16, right: u16) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub register: Register,
+    pub comparison: Comparison,
+    pub value: u16,
+}
+
+impl Condition {
+    pub fn holds(&self, cpu: &Chip8) -> bool {
+        self.comparison.holds(self.register.read(cpu), self.value)
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {:#x}", self.register, self.comparison.symbol(), self.value)
+    }
+}
+
+/*
+    Stops before the instruction at pc, if only when the condition holds.
+    A breakpoint with just a condition fires when the condition becomes true,
+    not on every instruction for as long as it stays true.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub pc: Option<u16>,
+    pub condition: Option<Condition>,
+    held: bool,
+}
+
+impl Breakpoint {
+    pub fn new(pc: Option<u16>, condition: Option<Condition>) -> Breakpoint {
+        Breakpoint { pc, condition, held: false }
+    }
+
+    // evaluated before every instruction, also updates the edge tracking
+    fn check(&mut self, cpu: &Chip8) -> bool {
+        let holds: bool = self.condition.is_none_or(|condition| condition.holds(cpu));
+        match self.pc {
+            Some(pc) => pc == cpu.get_pc() && holds,
+            None => {
+                let fired: bool = holds && !self.held;
+                self.held = holds;
+                fired
+            }
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.pc, self.condition) {
+            (Some(pc), Some(condition)) => write!(f, "at {:#05x} if {}", pc, condition),
+            (Some(pc), None) => write!(f, "at {:#05x}", pc),
+            (None, Some(condition)) => write!(f, "when {}", condition),
+            (None, None) => write!(f, "never"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCommand {
+    Continue,
+    Pause,
+    Step,
+    StepOver,
+    StepOut,
+    Break(Breakpoint),
+    Delete(usize),
+    Watch(Watchpoint),
+    DeleteWatch(usize),
+    List,
+    Registers,
+    Memory { address: usize, length: usize },
+    Help,
+}
+
+impl DebugCommand {
+
+    /*
+        Parse one line typed by the user, errors are messages meant for them
+    */
+    pub fn parse(line: &str) -> Result<DebugCommand, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let command: &str = match words.first() {
+            Some(command) => command,
+            None => return Err(String::from("empty command")),
+        };
+        let arguments: &[&str] = &words[1..];
+
+        match command {
+            "c" | "continue" => Ok(DebugCommand::Continue),
+            "p" | "pause" => Ok(DebugCommand::Pause),
+            "s" | "step" => Ok(DebugCommand::Step),
+            "n" | "next" => Ok(DebugCommand::StepOver),
+            "o" | "out" | "finish" => Ok(DebugCommand::StepOut),
+            "b" | "break" => parse_breakpoint(arguments).map(DebugCommand::Break),
+            "d" | "delete" => match arguments {
+                [number] => number.parse().map(DebugCommand::Delete).map_err(|_| format!("bad breakpoint number '{}'", number)),
+                _ => Err(String::from("usage: d N")),
+            },
+            "w" | "watch" => Watchpoint::parse(&arguments.join(" ")).map(DebugCommand::Watch),
+            "wd" => match arguments {
+                [number] => number.parse().map(DebugCommand::DeleteWatch).map_err(|_| format!("bad watchpoint number '{}'", number)),
+                _ => Err(String::from("usage: wd N")),
+            },
+            "l" | "list" => Ok(DebugCommand::List),
+            "r" | "regs" => Ok(DebugCommand::Registers),
+            "x" => match arguments {
+                [address] => Ok(DebugCommand::Memory { address: parse_address(address)?, length: 16 }),
+                [address, length] => Ok(DebugCommand::Memory { address: parse_address(address)?, length: parse_value(length)? as usize }),
+                _ => Err(String::from("usage: x ADDR [LEN]")),
+            },
+            "h" | "help" | "?" => Ok(DebugCommand::Help),
+            _ => Err(format!("unknown command '{}', try help", command)),
+        }
+    }
+}
+
+fn parse_breakpoint(arguments: &[&str]) -> Result<Breakpoint, String> {
+    match arguments {
+        [address] => Ok(Breakpoint::new(Some(parse_pc(address)?), None)),
+        [address, "if", condition @ ..] => Ok(Breakpoint::new(Some(parse_pc(address)?), Some(parse_condition(condition)?))),
+        ["if", condition @ ..] => Ok(Breakpoint::new(None, Some(parse_condition(condition)?))),
+        _ => Err(String::from("usage: b ADDR [if COND] or b if COND")),
+    }
+}
+
+fn parse_condition(words: &[&str]) -> Result<Condition, String> {
+    match words {
+        [register, symbol, value] => Ok(Condition {
+            register: Register::from_name(register).ok_or_else(|| format!("unknown register '{}'", register))?,
+            comparison: Comparison::from_symbol(symbol).ok_or_else(|| format!("unknown comparison '{}'", symbol))?,
+            value: parse_value(value)?,
+        }),
+        _ => Err(String::from("conditions look like 'v3 == 5'")),
+    }
+}
+
+// addresses are always hex, with or without 0x
+fn parse_address(text: &str) -> Result<usize, String> {
+    usize::from_str_radix(text.trim_start_matches("0x"), 16).map_err(|_| format!("bad address '{}'", text))
+}
+
+// the PC is 16 bits, anything wider can never be hit
+fn parse_pc(text: &str) -> Result<u16, String> {
+    u16::from_str_radix(text.trim_start_matches("0x"), 16).map_err(|_| format!("bad address '{}'", text))
+}
+
+// values are decimal unless they start with 0x
+fn parse_value(text: &str) -> Result<u16, String> {
+    let parsed = match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| format!("bad value '{}'", text))
+}
+
+/*
+    Why execution stopped in the middle of a frame
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakReason {
+    Breakpoint(usize),
+    Watchpoint(usize),
+    Step,
+}
+
+impl fmt::Display for BreakReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BreakReason::Breakpoint(index) => write!(f, "hit breakpoint {}", index),
+            BreakReason::Watchpoint(index) => write!(f, "hit watchpoint {}", index),
+            BreakReason::Step => write!(f, "step done"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugEvent {
+    // paused, nothing ran
+    Idle,
+    // a whole frame ran
+    Frame,
+    // execution stopped and the debugger is paused again
+    Break(BreakReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepTarget {
+    // the next instruction
+    Into,
+    // back at the instruction after a call, at the same stack depth
+    Over { pc: u16, sp: u8 },
+    // the stack is shallower than when stepping out started
+    Out { sp: u8 },
+}
+
+pub struct Debugger {
+    paused: bool,
+    step: Option<StepTarget>,
+    // resuming from a breakpoint must not hit it again straight away
+    skip_breakpoints: bool,
+    in_frame: bool,
+    cycles_left: u32,
+    breakpoints: Vec<Option<Breakpoint>>,
+}
+
+impl Default for Debugger {
+    fn default() -> Debugger {
+        Debugger::new()
+    }
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            paused: false,
+            step: None,
+            skip_breakpoints: false,
+            in_frame: false,
+            cycles_left: 0,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused && self.step.is_none()
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.step = None;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.step = None;
+        self.skip_breakpoints = true;
+    }
+
+    pub fn step(&mut self) {
+        self.start_step(StepTarget::Into);
+    }
+
+    /*
+        Like step, but a 2NNN call runs until it returns
+    */
+    pub fn step_over(&mut self, cpu: &Chip8) {
+        if let Some(Instruction::Call { .. }) = current_instruction(cpu) {
+            self.start_step(StepTarget::Over { pc: cpu.get_pc().wrapping_add(2), sp: cpu.get_sp() });
+        } else {
+            self.step();
+        }
+    }
+
+    /*
+        Run until the current subroutine returns, false when not in one
+    */
+    pub fn step_out(&mut self, cpu: &Chip8) -> bool {
+        if cpu.get_sp() == 0 {
+            return false;
+        }
+        self.start_step(StepTarget::Out { sp: cpu.get_sp() });
+        true
+    }
+
+    fn start_step(&mut self, target: StepTarget) {
+        self.paused = true;
+        self.step = Some(target);
+        self.skip_breakpoints = true;
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(Some(breakpoint));
+        self.breakpoints.len() - 1
+    }
+
+    // numbers of the remaining breakpoints don't change
+    pub fn remove_breakpoint(&mut self, index: usize) -> bool {
+        match self.breakpoints.get_mut(index) {
+            Some(slot) if slot.is_some() => {
+                *slot = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = (usize, &Breakpoint)> {
+        self.breakpoints.iter().enumerate().filter_map(|(index, slot)| slot.as_ref().map(|breakpoint| (index, breakpoint)))
+    }
+
+    /*
+        Forget the partly run frame, call after loading a state into the cpu
+    */
+    pub fn reset_frame(&mut self) {
+        self.in_frame = false;
+        self.cycles_left = 0;
+    }
+
+    /*
+        Run the rest of the current frame unless paused, stopping early at a
+        breakpoint or when a step is done
+    */
+    pub fn run_frame(&mut self, cpu: &mut Chip8) -> Result<DebugEvent, Chip8Error> {
+        if self.is_paused() {
+            return Ok(DebugEvent::Idle);
+        }
+        if !self.in_frame {
+            self.cycles_left = cpu.start_frame();
+            self.in_frame = true;
+        }
+
+        while self.cycles_left > 0 && !cpu.has_exited() {
+            let hit: Option<usize> = self.check_breakpoints(cpu);
+            if let (Some(index), false) = (hit, self.skip_breakpoints) {
+                self.pause();
+                return Ok(DebugEvent::Break(BreakReason::Breakpoint(index)));
+            }
+            self.skip_breakpoints = false;
+
+            cpu.emulate_cycle()?;
+            self.cycles_left -= 1;
+
+            let reason: Option<BreakReason> = match cpu.watchpoints_mut().take_break() {
+                Some(index) => Some(BreakReason::Watchpoint(index)),
+                None if self.step.is_some_and(|target| step_done(target, cpu)) => Some(BreakReason::Step),
+                None => None,
+            };
+            if let Some(reason) = reason {
+                self.pause();
+                if self.cycles_left == 0 {
+                    self.finish_frame(cpu);
+                }
+                return Ok(DebugEvent::Break(reason));
+            }
+        }
+
+        self.finish_frame(cpu);
+        Ok(DebugEvent::Frame)
+    }
+
+    fn finish_frame(&mut self, cpu: &mut Chip8) {
+        cpu.end_frame();
+        self.in_frame = false;
+    }
+
+    // every breakpoint is checked so the edge tracking of conditions stays current
+    fn check_breakpoints(&mut self, cpu: &Chip8) -> Option<usize> {
+        let mut hit: Option<usize> = None;
+        for (index, slot) in self.breakpoints.iter_mut().enumerate() {
+            if let Some(breakpoint) = slot {
+                if breakpoint.check(cpu) && hit.is_none() {
+                    hit = Some(index);
+                }
+            }
+        }
+        hit
+    }
+
+    /*
+        Carry out a command, returns what to show the user
+    */
+    pub fn execute(&mut self, command: DebugCommand, cpu: &mut Chip8) -> String {
+        match command {
+            DebugCommand::Continue => {
+                self.resume();
+                String::from("running")
+            }
+            DebugCommand::Pause => {
+                self.pause();
+                register_view(cpu).join("\n")
+            }
+            DebugCommand::Step => {
+                self.step();
+                String::new()
+            }
+            DebugCommand::StepOver => {
+                self.step_over(cpu);
+                String::new()
+            }
+            DebugCommand::StepOut => match self.step_out(cpu) {
+                true => String::new(),
+                false => String::from("not inside a subroutine"),
+            },
+            DebugCommand::Break(breakpoint) => {
+                let index: usize = self.add_breakpoint(breakpoint);
+                format!("breakpoint {} {}", index, breakpoint)
+            }
+            DebugCommand::Delete(index) => match self.remove_breakpoint(index) {
+                true => format!("deleted breakpoint {}", index),
+                false => format!("no breakpoint {}", index),
+            },
+            DebugCommand::Watch(watchpoint) => {
+                let index: usize = cpu.watchpoints_mut().add(watchpoint);
+                format!("watchpoint {} {}", index, watchpoint)
+            }
+            DebugCommand::DeleteWatch(index) => match cpu.watchpoints_mut().remove(index) {
+                true => format!("deleted watchpoint {}", index),
+                false => format!("no watchpoint {}", index),
+            },
+            DebugCommand::List => {
+                let breakpoints = self.breakpoints().map(|(index, breakpoint)| format!("breakpoint {}: {}", index, breakpoint));
+                let watchpoints = cpu.watchpoints().iter().map(|(index, watchpoint)| format!("watchpoint {}: {}", index, watchpoint));
+                let lines: Vec<String> = breakpoints.chain(watchpoints).collect();
+                match lines.is_empty() {
+                    true => String::from("no breakpoints or watchpoints"),
+                    false => lines.join("\n"),
+                }
+            }
+            DebugCommand::Registers => register_view(cpu).join("\n"),
+            DebugCommand::Memory { address, length } => memory_view(cpu, address, length),
+            DebugCommand::Help => String::from(HELP),
+        }
+    }
+}
+
+fn step_done(target: StepTarget, cpu: &Chip8) -> bool {
+    match target {
+        StepTarget::Into => true,
+        StepTarget::Over { pc, sp } => cpu.get_pc() == pc && cpu.get_sp() == sp,
+        StepTarget::Out { sp } => cpu.get_sp() < sp,
+    }
+}
+
+/*
+    The instruction at pc, None if the bytes there don't decode
+*/
+pub fn current_instruction(cpu: &Chip8) -> Option<Instruction> {
+    let pc: usize = cpu.get_pc() as usize;
+    let memory: &[u8] = cpu.get_memory();
+    if pc + 1 >= memory.len() {
+        return None;
+    }
+    decode((memory[pc] as u16) << 8 | memory[pc + 1] as u16)
+}
+
+/*
+    Registers, timers and the stack as short upper case lines, small enough
+    for the overlay font as well as the terminal
+*/
+pub fn register_view(cpu: &Chip8) -> Vec<String> {
+    let v: Vec<String> = cpu.get_v().iter().map(|value| format!("{:02X}", value)).collect();
+    let pc: usize = cpu.get_pc() as usize;
+    let memory: &[u8] = cpu.get_memory();
+    let next: String = match (memory.get(pc), memory.get(pc + 1)) {
+        (Some(high), Some(low)) => format!("{:02X}{:02X}", high, low),
+        _ => String::from("----"),
+    };
+    let sp: usize = cpu.get_sp() as usize;
+    let stack: Vec<String> = cpu.get_stack()[..sp].iter().map(|address| format!("{:04X}", address)).collect();
+
+    vec![
+        format!("PC {:04X} NEXT {} I {:04X}", pc, next, cpu.get_i()),
+        format!("V0-7 {}", v[..8].join(" ")),
+        format!("V8-F {}", v[8..].join(" ")),
+        format!("SP {:X} DT {:02X} ST {:02X}", sp, cpu.get_delay_timer(), cpu.get_sound_timer()),
+        format!("STACK {}", if stack.is_empty() { String::from("-") } else { stack.join(" ") }),
+    ]
+}
+
+fn memory_view(cpu: &Chip8, address: usize, length: usize) -> String {
+    let memory: &[u8] = cpu.get_memory();
+    if address >= memory.len() {
+        return format!("address {:#x} is outside memory", address);
+    }
+    let end: usize = memory.len().min(address.saturating_add(length));
+    let lines: Vec<String> = (address..end).step_by(16).map(|row| {
+        let bytes: Vec<String> = memory[row..end.min(row + 16)].iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("{:04x}: {}", row, bytes.join(" "))
+    }).collect();
+    lines.join("\n")
+}
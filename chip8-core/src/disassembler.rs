@@ -0,0 +1,362 @@
+use std::collections::BTreeSet;
+
+use crate::instruction::{decode, Instruction};
+
+/*
+    Turns a ROM back into source. Code is told apart from data by following
+    every path the program can take from 0x200: jumps, calls, both sides of
+    a skip and the tables BNNN jumps into. Whatever is never reached is data.
+    Jump and call targets get labels, as do addresses loaded into I, and the
+    data behind those is drawn as sprite art in the comments.
+*/
+
+const ORIGIN: usize = 0x200;
+// width of the instruction column before the address comment
+const COMMENT_COLUMN: usize = 28;
+// data bytes nothing points at are listed this many to a line
+const BYTES_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    // Cowgod's technical reference: CLS, LD V0, #05, DRW V1, V2, 5
+    Cowgod,
+    // Octo: clear, v0 := 0x05, sprite v1 v2 5
+    Octo,
+}
+
+impl Syntax {
+    pub fn from_name(name: &str) -> Option<Syntax> {
+        match name.to_ascii_lowercase().as_str() {
+            "cowgod" | "classic" => Some(Syntax::Cowgod),
+            "octo" => Some(Syntax::Octo),
+            _ => None,
+        }
+    }
+
+    fn comment(self) -> char {
+        match self {
+            Syntax::Cowgod => ';',
+            Syntax::Octo => '#',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteKind {
+    Data,
+    // first byte of an instruction
+    Code,
+    // rest of an instruction
+    Operand,
+}
+
+struct Analysis {
+    kinds: Vec<ByteKind>,
+    code_labels: BTreeSet<usize>,
+    data_labels: BTreeSet<usize>,
+}
+
+impl Analysis {
+    fn new(rom: &[u8]) -> Analysis {
+        let mut analysis = Analysis {
+            kinds: vec![ByteKind::Data; rom.len()],
+            code_labels: BTreeSet::new(),
+            data_labels: BTreeSet::new(),
+        };
+        analysis.trace(rom);
+
+        // a label can only be placed where a line starts, never inside an instruction
+        let kinds: &[ByteKind] = &analysis.kinds;
+        let placeable = |address: &usize| address.checked_sub(ORIGIN).and_then(|index| kinds.get(index)).is_some_and(|kind| *kind != ByteKind::Operand);
+        analysis.code_labels.retain(placeable);
+        analysis.data_labels.retain(placeable);
+        let code_labels: &BTreeSet<usize> = &analysis.code_labels;
+        analysis.data_labels.retain(|address| !code_labels.contains(address));
+        analysis
+    }
+
+    fn trace(&mut self, rom: &[u8]) {
+        let mut pending: Vec<usize> = vec![ORIGIN];
+        while let Some(address) = pending.pop() {
+            let index: usize = match address.checked_sub(ORIGIN) {
+                Some(index) if index + 1 < rom.len() => index,
+                _ => continue,
+            };
+            if self.kinds[index] != ByteKind::Data {
+                continue;
+            }
+            let instruction: Instruction = match decode(word(rom, index)) {
+                Some(instruction) => instruction,
+                None => continue,
+            };
+            let length: usize = instruction.length();
+            if index + length > rom.len() || self.kinds[index..index + length].iter().any(|kind| *kind != ByteKind::Data) {
+                continue;
+            }
+            self.kinds[index] = ByteKind::Code;
+            for kind in &mut self.kinds[index + 1..index + length] {
+                *kind = ByteKind::Operand;
+            }
+
+            let next: usize = address + length;
+            match instruction {
+                Instruction::Jump { nnn } => {
+                    self.code_labels.insert(nnn as usize);
+                    pending.push(nnn as usize);
+                }
+                Instruction::Call { nnn } => {
+                    self.code_labels.insert(nnn as usize);
+                    pending.push(nnn as usize);
+                    pending.push(next);
+                }
+                // usually the start of a table of jumps
+                Instruction::JumpOffset { nnn } => {
+                    self.code_labels.insert(nnn as usize);
+                    pending.push(nnn as usize);
+                }
+                Instruction::Return | Instruction::Exit => {}
+                Instruction::SkipIfEqual { .. }
+                | Instruction::SkipIfNotEqual { .. }
+                | Instruction::SkipIfRegistersEqual { .. }
+                | Instruction::SkipIfRegistersNotEqual { .. }
+                | Instruction::SkipIfKey { .. }
+                | Instruction::SkipIfNotKey { .. } => {
+                    let skipped: usize = match next.checked_sub(ORIGIN).filter(|index| index + 1 < rom.len()) {
+                        Some(index) if word(rom, index) == 0xF000 => 4,
+                        _ => 2,
+                    };
+                    pending.push(next + skipped);
+                    pending.push(next);
+                }
+                Instruction::SetIndex { nnn } => {
+                    self.data_labels.insert(nnn as usize);
+                    pending.push(next);
+                }
+                Instruction::SetIndexLong => {
+                    self.data_labels.insert(word(rom, index + 2) as usize);
+                    pending.push(next);
+                }
+                _ => pending.push(next),
+            }
+        }
+    }
+
+    fn label(&self, address: usize) -> Option<String> {
+        if self.code_labels.contains(&address) {
+            Some(format!("L{:03X}", address))
+        } else if self.data_labels.contains(&address) {
+            Some(format!("D{:03X}", address))
+        } else {
+            None
+        }
+    }
+}
+
+fn word(rom: &[u8], index: usize) -> u16 {
+    (rom[index] as u16) << 8 | rom[index + 1] as u16
+}
+
+/*
+    Disassemble a ROM loaded at 0x200 into source for the given syntax
+*/
+pub fn disassemble(rom: &[u8], syntax: Syntax) -> String {
+    let analysis = Analysis::new(rom);
+    let comment: char = syntax.comment();
+    let mut out: String = format!("{} {} bytes loaded at 0x{:03X}\n", comment, rom.len(), ORIGIN);
+    if syntax == Syntax::Octo {
+        out.push_str("\n: main\n");
+    }
+
+    // sprite art is drawn for data that I is pointed at, until the next label or code
+    let mut in_sprite: bool = false;
+    let mut index: usize = 0;
+    while index < rom.len() {
+        let address: usize = ORIGIN + index;
+        if let Some(label) = analysis.label(address) {
+            match syntax {
+                Syntax::Cowgod => out.push_str(&format!("\n{}:\n", label)),
+                Syntax::Octo => out.push_str(&format!("\n: {}\n", label)),
+            }
+            in_sprite = analysis.data_labels.contains(&address);
+        }
+
+        if analysis.kinds[index] == ByteKind::Code {
+            in_sprite = false;
+            let opcode: u16 = word(rom, index);
+            let instruction: Instruction = decode(opcode).unwrap_or(Instruction::Sys { nnn: opcode });
+            let length: usize = instruction.length();
+            let operand: u16 = if length == 4 { word(rom, index + 2) } else { 0 };
+            let name = |target: u16| analysis.label(target as usize);
+            let text: String = match syntax {
+                Syntax::Cowgod => cowgod(instruction, operand, &name),
+                Syntax::Octo => octo(instruction, opcode, operand, &name),
+            };
+            let bytes: String = rom[index..index + length].iter().map(|byte| format!("{:02X}", byte)).collect();
+            out.push_str(&line(&text, comment, &format!("{:03X}  {}", address, bytes)));
+            index += length;
+            continue;
+        }
+
+        if in_sprite {
+            let art: String = (0..8).map(|bit| if rom[index] & (0x80 >> bit) != 0 { '#' } else { '.' }).collect();
+            out.push_str(&line(&data(syntax, &rom[index..index + 1]), comment, &format!("{:03X}  {}", address, art)));
+            index += 1;
+            continue;
+        }
+
+        // plain data runs until the next label, code or line boundary
+        let mut end: usize = index + 1;
+        while end < rom.len() && end - index < BYTES_PER_LINE
+            && analysis.kinds[end] == ByteKind::Data && analysis.label(ORIGIN + end).is_none() {
+            end += 1;
+        }
+        out.push_str(&line(&data(syntax, &rom[index..end]), comment, &format!("{:03X}", address)));
+        index = end;
+    }
+    out
+}
+
+fn line(text: &str, comment: char, note: &str) -> String {
+    format!("    {:width$} {} {}\n", text, comment, note, width = COMMENT_COLUMN - 1)
+}
+
+fn data(syntax: Syntax, bytes: &[u8]) -> String {
+    match syntax {
+        Syntax::Cowgod => {
+            let values: Vec<String> = bytes.iter().map(|byte| format!("#{:02X}", byte)).collect();
+            format!("DB {}", values.join(", "))
+        }
+        Syntax::Octo => {
+            let values: Vec<String> = bytes.iter().map(|byte| format!("0x{:02X}", byte)).collect();
+            values.join(" ")
+        }
+    }
+}
+
+/*
+    Cowgod mnemonic of a single instruction, without labels. operand is the
+    address following F000.
+*/
+pub fn mnemonic(instruction: Instruction, operand: u16) -> String {
+    cowgod(instruction, operand, &|_| None)
+}
+
+fn cowgod(instruction: Instruction, operand: u16, name: &dyn Fn(u16) -> Option<String>) -> String {
+    let address = |nnn: u16| name(nnn).unwrap_or_else(|| format!("#{:03X}", nnn));
+    match instruction {
+        Instruction::Sys { nnn } => format!("SYS {}", address(nnn)),
+        Instruction::Clear => String::from("CLS"),
+        Instruction::Return => String::from("RET"),
+        Instruction::ScrollDown { n } => format!("SCD {}", n),
+        Instruction::ScrollRight => String::from("SCR"),
+        Instruction::ScrollLeft => String::from("SCL"),
+        Instruction::Exit => String::from("EXIT"),
+        Instruction::Lores => String::from("LOW"),
+        Instruction::Hires => String::from("HIGH"),
+        Instruction::Jump { nnn } => format!("JP {}", address(nnn)),
+        Instruction::Call { nnn } => format!("CALL {}", address(nnn)),
+        Instruction::SkipIfEqual { x, nn } => format!("SE V{:X}, #{:02X}", x, nn),
+        Instruction::SkipIfNotEqual { x, nn } => format!("SNE V{:X}, #{:02X}", x, nn),
+        Instruction::SkipIfRegistersEqual { x, y } => format!("SE V{:X}, V{:X}", x, y),
+        Instruction::SaveRange { x, y } => format!("SAVE V{:X}, V{:X}", x, y),
+        Instruction::LoadRange { x, y } => format!("LOAD V{:X}, V{:X}", x, y),
+        Instruction::Set { x, nn } => format!("LD V{:X}, #{:02X}", x, nn),
+        Instruction::AddImmediate { x, nn } => format!("ADD V{:X}, #{:02X}", x, nn),
+        Instruction::Copy { x, y } => format!("LD V{:X}, V{:X}", x, y),
+        Instruction::Or { x, y } => format!("OR V{:X}, V{:X}", x, y),
+        Instruction::And { x, y } => format!("AND V{:X}, V{:X}", x, y),
+        Instruction::Xor { x, y } => format!("XOR V{:X}, V{:X}", x, y),
+        Instruction::Add { x, y } => format!("ADD V{:X}, V{:X}", x, y),
+        Instruction::Subtract { x, y } => format!("SUB V{:X}, V{:X}", x, y),
+        Instruction::ShiftRight { x, y } => format!("SHR V{:X}, V{:X}", x, y),
+        Instruction::SubtractReversed { x, y } => format!("SUBN V{:X}, V{:X}", x, y),
+        Instruction::ShiftLeft { x, y } => format!("SHL V{:X}, V{:X}", x, y),
+        Instruction::SkipIfRegistersNotEqual { x, y } => format!("SNE V{:X}, V{:X}", x, y),
+        Instruction::SetIndex { nnn } => format!("LD I, {}", address(nnn)),
+        Instruction::JumpOffset { nnn } => format!("JP V0, {}", address(nnn)),
+        Instruction::Random { x, nn } => format!("RND V{:X}, #{:02X}", x, nn),
+        Instruction::Draw { x, y, n } => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        Instruction::SkipIfKey { x } => format!("SKP V{:X}", x),
+        Instruction::SkipIfNotKey { x } => format!("SKNP V{:X}", x),
+        Instruction::SetIndexLong => format!("LD I, LONG {}", name(operand).unwrap_or_else(|| format!("#{:04X}", operand))),
+        Instruction::SelectPlanes { n } => format!("PLANE {}", n),
+        Instruction::LoadAudio => String::from("AUDIO"),
+        Instruction::GetDelay { x } => format!("LD V{:X}, DT", x),
+        Instruction::WaitKey { x } => format!("LD V{:X}, K", x),
+        Instruction::SetDelay { x } => format!("LD DT, V{:X}", x),
+        Instruction::SetSound { x } => format!("LD ST, V{:X}", x),
+        Instruction::AddIndex { x } => format!("ADD I, V{:X}", x),
+        Instruction::FontCharacter { x } => format!("LD F, V{:X}", x),
+        Instruction::BigFontCharacter { x } => format!("LD HF, V{:X}", x),
+        Instruction::Bcd { x } => format!("LD B, V{:X}", x),
+        Instruction::SetPitch { x } => format!("PITCH V{:X}", x),
+        Instruction::Store { x } => format!("LD [I], V{:X}", x),
+        Instruction::Load { x } => format!("LD V{:X}, [I]", x),
+        Instruction::SaveFlags { x } => format!("LD R, V{:X}", x),
+        Instruction::LoadFlags { x } => format!("LD V{:X}, R", x),
+    }
+}
+
+/*
+    Octo spells skips as the condition for running the next instruction, so
+    3XNN (skip if equal) reads "if vX != NN then"
+*/
+fn octo(instruction: Instruction, opcode: u16, operand: u16, name: &dyn Fn(u16) -> Option<String>) -> String {
+    let address = |nnn: u16| name(nnn).unwrap_or_else(|| format!("0x{:03X}", nnn));
+    match instruction {
+        // Octo has no way to call machine code, keep the bytes
+        Instruction::Sys { .. } => format!("0x{:02X} 0x{:02X}", opcode >> 8, opcode & 0xFF),
+        Instruction::Clear => String::from("clear"),
+        Instruction::Return => String::from("return"),
+        Instruction::ScrollDown { n } => format!("scroll-down {}", n),
+        Instruction::ScrollRight => String::from("scroll-right"),
+        Instruction::ScrollLeft => String::from("scroll-left"),
+        Instruction::Exit => String::from("exit"),
+        Instruction::Lores => String::from("lores"),
+        Instruction::Hires => String::from("hires"),
+        Instruction::Jump { nnn } => format!("jump {}", address(nnn)),
+        Instruction::Call { nnn } => match name(nnn) {
+            Some(label) => label,
+            None => format!(":call 0x{:03X}", nnn),
+        },
+        Instruction::SkipIfEqual { x, nn } => format!("if v{:x} != 0x{:02X} then", x, nn),
+        Instruction::SkipIfNotEqual { x, nn } => format!("if v{:x} == 0x{:02X} then", x, nn),
+        Instruction::SkipIfRegistersEqual { x, y } => format!("if v{:x} != v{:x} then", x, y),
+        Instruction::SaveRange { x, y } => format!("save v{:x} - v{:x}", x, y),
+        Instruction::LoadRange { x, y } => format!("load v{:x} - v{:x}", x, y),
+        Instruction::Set { x, nn } => format!("v{:x} := 0x{:02X}", x, nn),
+        Instruction::AddImmediate { x, nn } => format!("v{:x} += 0x{:02X}", x, nn),
+        Instruction::Copy { x, y } => format!("v{:x} := v{:x}", x, y),
+        Instruction::Or { x, y } => format!("v{:x} |= v{:x}", x, y),
+        Instruction::And { x, y } => format!("v{:x} &= v{:x}", x, y),
+        Instruction::Xor { x, y } => format!("v{:x} ^= v{:x}", x, y),
+        Instruction::Add { x, y } => format!("v{:x} += v{:x}", x, y),
+        Instruction::Subtract { x, y } => format!("v{:x} -= v{:x}", x, y),
+        Instruction::ShiftRight { x, y } => format!("v{:x} >>= v{:x}", x, y),
+        Instruction::SubtractReversed { x, y } => format!("v{:x} =- v{:x}", x, y),
+        Instruction::ShiftLeft { x, y } => format!("v{:x} <<= v{:x}", x, y),
+        Instruction::SkipIfRegistersNotEqual { x, y } => format!("if v{:x} == v{:x} then", x, y),
+        Instruction::SetIndex { nnn } => format!("i := {}", address(nnn)),
+        Instruction::JumpOffset { nnn } => format!("jump0 {}", address(nnn)),
+        Instruction::Random { x, nn } => format!("v{:x} := random 0x{:02X}", x, nn),
+        Instruction::Draw { x, y, n } => format!("sprite v{:x} v{:x} {}", x, y, n),
+        Instruction::SkipIfKey { x } => format!("if v{:x} -key then", x),
+        Instruction::SkipIfNotKey { x } => format!("if v{:x} key then", x),
+        Instruction::SetIndexLong => format!("i := long {}", name(operand).unwrap_or_else(|| format!("0x{:04X}", operand))),
+        Instruction::SelectPlanes { n } => format!("plane {}", n),
+        Instruction::LoadAudio => String::from("audio"),
+        Instruction::GetDelay { x } => format!("v{:x} := delay", x),
+        Instruction::WaitKey { x } => format!("v{:x} := key", x),
+        Instruction::SetDelay { x } => format!("delay := v{:x}", x),
+        Instruction::SetSound { x } => format!("buzzer := v{:x}", x),
+        Instruction::AddIndex { x } => format!("i += v{:x}", x),
+        Instruction::FontCharacter { x } => format!("i := hex v{:x}", x),
+        Instruction::BigFontCharacter { x } => format!("i := bighex v{:x}", x),
+        Instruction::Bcd { x } => format!("bcd v{:x}", x),
+        Instruction::SetPitch { x } => format!("pitch := v{:x}", x),
+        Instruction::Store { x } => format!("save v{:x}", x),
+        Instruction::Load { x } => format!("load v{:x}", x),
+        Instruction::SaveFlags { x } => format!("saveflags v{:x}", x),
+        Instruction::LoadFlags { x } => format!("loadflags v{:x}", x),
+    }
+}
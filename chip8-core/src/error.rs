@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/*
+    Errors raised while loading or executing a program. Each execution error
+    carries the PC of the offending instruction so a frontend can report it
+    and leave the machine paused for inspection.
+*/
+#[derive(Debug)]
+pub enum Chip8Error {
+    UnknownOpcode { opcode: u16, pc: u16 },
+    StackOverflow { pc: u16 },
+    StackUnderflow { pc: u16 },
+    MemoryOutOfBounds { address: usize, pc: u16 },
+    RomTooLarge { size: usize, max: usize },
+    Io { path: String, source: io::Error },
+    BadSaveState { reason: String },
+    BadMovie { reason: String },
+    BadTrace { reason: String },
+    BadRegister { reason: String },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Chip8Error::UnknownOpcode { opcode, pc } => {
+                write!(f, "unknown opcode {:#06x} at pc {:#05x}", opcode, pc)
+            }
+            Chip8Error::StackOverflow { pc } => {
+                write!(f, "stack overflow at pc {:#05x}", pc)
+            }
+            Chip8Error::StackUnderflow { pc } => {
+                write!(f, "stack underflow at pc {:#05x}", pc)
+            }
+            Chip8Error::MemoryOutOfBounds { address, pc } => {
+                write!(f, "memory access out of bounds at {:#x} (pc {:#05x})", address, pc)
+            }
+            Chip8Error::RomTooLarge { size, max } => {
+                write!(f, "rom is {} bytes, at most {} bytes fit in memory", size, max)
+            }
+            Chip8Error::Io { path, source } => {
+                write!(f, "failed to read file {}: {}", path, source)
+            }
+            Chip8Error::BadSaveState { reason } => {
+                write!(f, "cannot restore save state: {}", reason)
+            }
+            Chip8Error::BadMovie { reason } => {
+                write!(f, "cannot replay movie: {}", reason)
+            }
+            Chip8Error::BadTrace { reason } => {
+                write!(f, "cannot read trace: {}", reason)
+            }
+            Chip8Error::BadRegister { reason } => {
+                write!(f, "cannot set register: {}", reason)
+            }
+        }
+    }
+}
+
+impl Error for Chip8Error {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Chip8Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,508 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+
+use crate::chip8::Chip8;
+use crate::debugger::{Breakpoint, Debugger, Register};
+use crate::trace::TRACED_REGISTERS;
+use crate::watch::{WatchAction, Watchpoint};
+
+/*
+    A GDB remote serial protocol server, so gdb and other frontends that speak
+    it can debug a running ROM. It rides on the Debugger: continue and step
+    resume it, software and hardware breakpoints are its breakpoints and
+    watchpoints go to the cpu's watchpoints, so execution still happens in
+    Chip8::emulate_cycle as the frontend runs its frames.
+
+    Nothing blocks: poll is called once per frame from the emulation loop,
+    accepts a connection, answers whatever packets arrived and sends the stop
+    reply once the debugger pauses again. Attaching pauses the ROM, detaching
+    removes the breakpoints gdb set and lets it run.
+
+    Registers are numbered as in traces, V0-VF are 0-15, then 16 I, 17 SP,
+    18 DT, 19 ST, and 20 PC. I and PC are 16 bits, the others 8, all big
+    endian like the rest of the machine. gdb has no CHIP-8 architecture, the
+    target description sent through qXfer:features:read names the registers
+    for frontends that read it. Memory is the address space of the current
+    mode, 4K for CHIP-8.
+
+    Supported packets: ?, g, G, p, P, m, M, c, s, Z0-Z4, z0-z4, D, k, the
+    break character, qSupported, qXfer:features:read, qAttached and
+    QStartNoAckMode. Anything else gets the empty "not supported" reply.
+*/
+
+const PACKET_SIZE: usize = 0x1000;
+
+// signals in stop replies
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GdbEvent {
+    Connected(SocketAddr),
+    Disconnected,
+    // the frontend asked to kill the program
+    Kill,
+}
+
+impl fmt::Display for GdbEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GdbEvent::Connected(address) => write!(f, "gdb connected from {}", address),
+            GdbEvent::Disconnected => write!(f, "gdb disconnected"),
+            GdbEvent::Kill => write!(f, "gdb killed the program"),
+        }
+    }
+}
+
+// what a packet asks of the connection besides its reply
+enum Outcome {
+    Reply(String),
+    // c or s, the reply comes when the debugger pauses
+    Resume,
+    Detach,
+    Kill,
+}
+
+// a breakpoint or watchpoint set with Z, by its kind, address and length
+struct Point {
+    kind: u8,
+    address: usize,
+    length: usize,
+    index: usize,
+}
+
+struct Connection {
+    stream: TcpStream,
+    input: Vec<u8>,
+    acks: bool,
+    // waiting for a stop reply
+    running: bool,
+    interrupted: bool,
+    points: Vec<Point>,
+}
+
+pub struct GdbServer {
+    listener: TcpListener,
+    connection: Option<Connection>,
+}
+
+impl GdbServer {
+    /*
+        Listen on address, e.g. "127.0.0.1:1234", one client at a time
+    */
+    pub fn listen(address: &str) -> io::Result<GdbServer> {
+        let listener: TcpListener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(GdbServer { listener, connection: None })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /*
+        Accept a client, answer its packets and report stops. halted is true
+        once the cpu stopped for good, after an error or when the program
+        exited.
+    */
+    pub fn poll(&mut self, debugger: &mut Debugger, cpu: &mut Chip8, halted: bool) -> Vec<GdbEvent> {
+        let mut events: Vec<GdbEvent> = Vec::new();
+        if self.connection.is_none() {
+            match self.accept() {
+                Ok(Some((connection, address))) => {
+                    debugger.pause();
+                    self.connection = Some(connection);
+                    events.push(GdbEvent::Connected(address));
+                }
+                Ok(None) => return events,
+                Err(_) => return events,
+            }
+        }
+
+        let connection: &mut Connection = self.connection.as_mut().unwrap();
+        let outcome: Option<Outcome> = match connection.receive() {
+            Ok(true) => connection.answer(debugger, cpu),
+            // closed or broken, either way the client is gone
+            Ok(false) | Err(_) => Some(Outcome::Detach),
+        };
+        let outcome: Option<Outcome> = outcome.or_else(|| connection.report_stop(debugger, cpu, halted).err().map(|_| Outcome::Detach));
+
+        match outcome {
+            Some(Outcome::Detach) => {
+                connection.remove_points(debugger, cpu);
+                debugger.resume();
+                self.connection = None;
+                events.push(GdbEvent::Disconnected);
+            }
+            Some(Outcome::Kill) => {
+                self.connection = None;
+                events.push(GdbEvent::Kill);
+            }
+            _ => {}
+        }
+        events
+    }
+
+    fn accept(&self) -> io::Result<Option<(Connection, SocketAddr)>> {
+        let (stream, address) = match self.listener.accept() {
+            Ok(accepted) => accepted,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        let connection = Connection { stream, input: Vec::new(), acks: true, running: false, interrupted: false, points: Vec::new() };
+        Ok(Some((connection, address)))
+    }
+}
+
+impl Connection {
+
+    // read what arrived, false once the client closed the connection
+    fn receive(&mut self) -> io::Result<bool> {
+        let mut buffer = [0u8;1024];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Ok(false),
+                Ok(count) => self.input.extend_from_slice(&buffer[..count]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(true),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /*
+        Handle every complete packet received, stopping early when one ends
+        the connection
+    */
+    fn answer(&mut self, debugger: &mut Debugger, cpu: &mut Chip8) -> Option<Outcome> {
+        loop {
+            let start: usize = match self.input.iter().position(|&byte| byte == b'$' || byte == 0x03) {
+                Some(start) => start,
+                None => {
+                    // acks and line noise
+                    self.input.clear();
+                    return None;
+                }
+            };
+            if self.input[start] == 0x03 {
+                self.input.drain(..=start);
+                debugger.pause();
+                self.interrupted = true;
+                continue;
+            }
+            let end: usize = match self.input[start..].iter().position(|&byte| byte == b'#') {
+                Some(end) if start + end + 2 < self.input.len() => start + end,
+                // wait for the rest of it
+                _ => return None,
+            };
+            let packet: Vec<u8> = self.input[start + 1..end].to_vec();
+            let checksum: Option<u8> = std::str::from_utf8(&self.input[end + 1..end + 3]).ok()
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+            self.input.drain(..end + 3);
+
+            if checksum != Some(checksum_of(&packet)) {
+                if self.acks && self.stream.write_all(b"-").is_err() {
+                    return Some(Outcome::Detach);
+                }
+                continue;
+            }
+            if self.acks && self.stream.write_all(b"+").is_err() {
+                return Some(Outcome::Detach);
+            }
+
+            let packet: String = String::from_utf8_lossy(&packet).into_owned();
+            let outcome: Outcome = self.handle(&packet, debugger, cpu);
+            let reply: io::Result<()> = match &outcome {
+                Outcome::Reply(reply) => self.send(reply),
+                Outcome::Detach => self.send("OK"),
+                Outcome::Resume | Outcome::Kill => Ok(()),
+            };
+            if packet == "QStartNoAckMode" {
+                self.acks = false;
+            }
+            match (reply, outcome) {
+                (Err(_), _) | (_, Outcome::Detach) => return Some(Outcome::Detach),
+                (_, Outcome::Kill) => return Some(Outcome::Kill),
+                (_, Outcome::Resume) => self.running = true,
+                (_, Outcome::Reply(_)) => {}
+            }
+        }
+    }
+
+    fn handle(&mut self, packet: &str, debugger: &mut Debugger, cpu: &mut Chip8) -> Outcome {
+        let (command, arguments): (char, &str) = match packet.chars().next() {
+            Some(command) => (command, &packet[command.len_utf8()..]),
+            None => return Outcome::Reply(String::new()),
+        };
+        let reply: Result<String, u8> = match command {
+            '?' => Ok(format!("S{:02x}", SIGTRAP)),
+            'g' => Ok(gdb_registers().map(|register| register_hex(register, cpu)).collect()),
+            'G' => write_registers(arguments, cpu).map(|_| String::from("OK")),
+            'p' => parse_hex(arguments).and_then(register_number).map(|register| register_hex(register, cpu)),
+            'P' => write_register(arguments, cpu).map(|_| String::from("OK")),
+            'm' => read_memory(arguments, cpu),
+            'M' => write_memory(arguments, cpu).map(|_| String::from("OK")),
+            'c' | 's' => {
+                if !arguments.is_empty() {
+                    match parse_hex(arguments) {
+                        Ok(address) => cpu.set_pc(address as u16),
+                        Err(code) => return Outcome::Reply(error(code)),
+                    }
+                }
+                if command == 'c' {
+                    debugger.resume();
+                } else {
+                    debugger.step();
+                }
+                self.interrupted = false;
+                return Outcome::Resume;
+            }
+            'Z' => self.insert_point(arguments, debugger, cpu).map(|_| String::from("OK")),
+            'z' => self.remove_point(arguments, debugger, cpu).map(|_| String::from("OK")),
+            'D' => return Outcome::Detach,
+            'k' => return Outcome::Kill,
+            'H' | 'T' => Ok(String::from("OK")),
+            _ => Ok(query(packet)),
+        };
+        Outcome::Reply(reply.unwrap_or_else(error))
+    }
+
+    // Z/z arguments are "kind,address,length"
+    fn parse_point(arguments: &str) -> Result<(u8, usize, usize), u8> {
+        let fields: Vec<&str> = arguments.split(',').collect();
+        match fields[..] {
+            [kind, address, length] => Ok((parse_hex(kind)? as u8, parse_hex(address)?, parse_hex(length)?)),
+            _ => Err(1),
+        }
+    }
+
+    fn insert_point(&mut self, arguments: &str, debugger: &mut Debugger, cpu: &mut Chip8) -> Result<(), u8> {
+        let (kind, address, length) = Connection::parse_point(arguments)?;
+        if address >= cpu.get_memory().len() {
+            return Err(1);
+        }
+        let index: usize = match kind {
+            0 | 1 => debugger.add_breakpoint(Breakpoint::new(Some(address as u16), None)),
+            2..=4 => cpu.watchpoints_mut().add(Watchpoint {
+                start: address,
+                end: address + length.max(1) - 1,
+                fetch: false,
+                read: kind != 2,
+                write: kind != 3,
+                action: WatchAction::Break,
+            }),
+            _ => return Err(1),
+        };
+        self.points.push(Point { kind, address, length, index });
+        Ok(())
+    }
+
+    fn remove_point(&mut self, arguments: &str, debugger: &mut Debugger, cpu: &mut Chip8) -> Result<(), u8> {
+        let (kind, address, length) = Connection::parse_point(arguments)?;
+        let position: usize = self.points.iter()
+            .position(|point| point.kind == kind && point.address == address && point.length == length)
+            .ok_or(1)?;
+        let point: Point = self.points.remove(position);
+        remove(&point, debugger, cpu);
+        Ok(())
+    }
+
+    fn remove_points(&mut self, debugger: &mut Debugger, cpu: &mut Chip8) {
+        for point in self.points.drain(..) {
+            remove(&point, debugger, cpu);
+        }
+    }
+
+    /*
+        Once a continue or step stopped, tell the client why
+    */
+    fn report_stop(&mut self, debugger: &Debugger, cpu: &Chip8, halted: bool) -> io::Result<()> {
+        if !self.running {
+            return Ok(());
+        }
+        let reply: String = if halted && cpu.has_exited() {
+            String::from("W00")
+        } else if halted {
+            format!("S{:02x}", SIGILL)
+        } else if debugger.is_paused() {
+            format!("S{:02x}", if self.interrupted { SIGINT } else { SIGTRAP })
+        } else {
+            return Ok(());
+        };
+        self.running = false;
+        self.interrupted = false;
+        self.send(&reply)
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let packet: String = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.stream.write_all(packet.as_bytes())
+    }
+}
+
+fn remove(point: &Point, debugger: &mut Debugger, cpu: &mut Chip8) {
+    match point.kind {
+        0 | 1 => {
+            debugger.remove_breakpoint(point.index);
+        }
+        _ => {
+            cpu.watchpoints_mut().remove(point.index);
+        }
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+fn error(code: u8) -> String {
+    format!("E{:02x}", code)
+}
+
+/*
+    The q and Q packets, general queries
+*/
+fn query(packet: &str) -> String {
+    if packet.starts_with("qSupported") {
+        return format!("PacketSize={:x};qXfer:features:read+;QStartNoAckMode+;swbreak+;hwbreak+", PACKET_SIZE);
+    }
+    if let Some(arguments) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+        return read_chunk(&target_description(), arguments).unwrap_or_else(error);
+    }
+    match packet {
+        "qAttached" => String::from("1"),
+        "QStartNoAckMode" => String::from("OK"),
+        _ => String::new(),
+    }
+}
+
+// qXfer replies are "m" with more to come or "l" for the last chunk
+fn read_chunk(document: &str, arguments: &str) -> Result<String, u8> {
+    let (offset, length) = arguments.split_once(',').ok_or(1)?;
+    let (offset, length) = (parse_hex(offset)?, parse_hex(length)?);
+    let start: usize = offset.min(document.len());
+    let end: usize = document.len().min(start.saturating_add(length));
+    let marker: char = if end < document.len() { 'm' } else { 'l' };
+    Ok(format!("{}{}", marker, &document[start..end]))
+}
+
+fn target_description() -> String {
+    let registers: String = gdb_registers().enumerate().map(|(number, register)| {
+        let (bits, kind) = match register {
+            Register::I => (16, "data_ptr"),
+            Register::Pc => (16, "code_ptr"),
+            _ => (8, "uint8"),
+        };
+        format!("<reg name=\"{}\" bitsize=\"{}\" regnum=\"{}\" type=\"{}\"/>", register, bits, number, kind)
+    }).collect();
+    format!("<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\"><target version=\"1.0\"><feature name=\"org.chip8.core\">{}</feature></target>", registers)
+}
+
+// registers in gdb's numbering
+fn gdb_registers() -> impl Iterator<Item = Register> {
+    TRACED_REGISTERS.iter().copied().chain(std::iter::once(Register::Pc))
+}
+
+fn register_number(number: usize) -> Result<Register, u8> {
+    gdb_registers().nth(number).ok_or(1)
+}
+
+fn register_size(register: Register) -> usize {
+    match register {
+        Register::I | Register::Pc => 2,
+        _ => 1,
+    }
+}
+
+fn register_hex(register: Register, cpu: &Chip8) -> String {
+    let value: u16 = register.read(cpu);
+    match register_size(register) {
+        2 => format!("{:04x}", value),
+        _ => format!("{:02x}", value),
+    }
+}
+
+fn set_register(register: Register, value: u16, cpu: &mut Chip8) -> Result<(), u8> {
+    register.write(cpu, value).map_err(|_| 2)
+}
+
+fn write_registers(hex: &str, cpu: &mut Chip8) -> Result<(), u8> {
+    let bytes: Vec<u8> = parse_bytes(hex)?;
+    if bytes.len() != gdb_registers().map(register_size).sum::<usize>() {
+        return Err(1);
+    }
+    let mut values: Vec<(Register, u16)> = Vec::new();
+    let mut position: usize = 0;
+    for register in gdb_registers() {
+        let size: usize = register_size(register);
+        values.push((register, bytes[position..position + size].iter().fold(0, |value, &byte| value << 8 | byte as u16)));
+        position += size;
+    }
+    // check the stack pointer before changing anything
+    if values.iter().any(|&(register, value)| register == Register::Sp && value as usize > cpu.get_stack().len()) {
+        return Err(2);
+    }
+    for (register, value) in values {
+        set_register(register, value, cpu)?;
+    }
+    Ok(())
+}
+
+// "number=value" with the value in the register's size
+fn write_register(arguments: &str, cpu: &mut Chip8) -> Result<(), u8> {
+    let (number, value) = arguments.split_once('=').ok_or(1)?;
+    let register: Register = register_number(parse_hex(number)?)?;
+    let bytes: Vec<u8> = parse_bytes(value)?;
+    if bytes.len() != register_size(register) {
+        return Err(1);
+    }
+    set_register(register, bytes.iter().fold(0, |value, &byte| value << 8 | byte as u16), cpu)
+}
+
+// "address,length", reads stop at the end of memory
+fn read_memory(arguments: &str, cpu: &Chip8) -> Result<String, u8> {
+    let (address, length) = arguments.split_once(',').ok_or(1)?;
+    let (address, length) = (parse_hex(address)?, parse_hex(length)?);
+    // two hex digits a byte, the reply has to fit in the packet size we told gdb
+    let length: usize = length.min(PACKET_SIZE / 2);
+    let memory: &[u8] = cpu.get_memory();
+    if address >= memory.len() && length > 0 {
+        return Err(1);
+    }
+    let end: usize = memory.len().min(address.saturating_add(length));
+    Ok(memory[address.min(end)..end].iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+// "address,length:bytes"
+fn write_memory(arguments: &str, cpu: &mut Chip8) -> Result<(), u8> {
+    let (range, data) = arguments.split_once(':').ok_or(1)?;
+    let (address, length) = range.split_once(',').ok_or(1)?;
+    let (address, length) = (parse_hex(address)?, parse_hex(length)?);
+    let bytes: Vec<u8> = parse_bytes(data)?;
+    if bytes.len() != length || address.saturating_add(length) > cpu.get_memory().len() {
+        return Err(1);
+    }
+    for (offset, byte) in bytes.into_iter().enumerate() {
+        cpu.set_memory(address + offset, byte).map_err(|_| 1)?;
+    }
+    Ok(())
+}
+
+fn parse_hex(text: &str) -> Result<usize, u8> {
+    usize::from_str_radix(text, 16).map_err(|_| 1)
+}
+
+fn parse_bytes(hex: &str) -> Result<Vec<u8>, u8> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err(1);
+    }
+    (0..hex.len()).step_by(2).map(|start| u8::from_str_radix(&hex[start..start + 2], 16).map_err(|_| 1)).collect()
+}
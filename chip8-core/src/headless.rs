@@ -0,0 +1,374 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::audio::{AudioSink, NullAudio};
+use crate::chip8::Chip8;
+use crate::error::Chip8Error;
+use crate::instruction::{decode, Instruction};
+use crate::watch::WatchHit;
+
+/*
+    Runs a program without a window, for regression testing ROMs on machines
+    with no display. Input comes from a script and the result is read back
+    as text, PNG or JSON once the run stops.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopCondition {
+    // stop after this many 60 Hz frames
+    Frames(u64),
+    // stop when the program jumps to itself, the usual way a ROM ends
+    PcLoop,
+    // stop before executing this opcode
+    Opcode(u16),
+    // stop after this much wall clock time
+    Timeout(Duration),
+}
+
+#[derive(Debug)]
+pub enum StopReason {
+    FrameLimit,
+    PcLoop(u16),
+    Opcode(u16),
+    Timeout,
+    Exited,
+    // a watchpoint set to break was hit by the instruction just run
+    Watchpoint(usize),
+    Error(Chip8Error),
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopReason::FrameLimit => write!(f, "frame limit reached"),
+            StopReason::PcLoop(pc) => write!(f, "infinite loop at {:#05x}", pc),
+            StopReason::Opcode(opcode) => write!(f, "reached opcode {:#06x}", opcode),
+            StopReason::Timeout => write!(f, "timed out"),
+            StopReason::Exited => write!(f, "program exited"),
+            StopReason::Watchpoint(index) => write!(f, "hit watchpoint {}", index),
+            StopReason::Error(e) => write!(f, "error: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    pub frame: u64,
+    pub key: u8,
+    pub pressed: bool,
+}
+
+/*
+    Key presses to feed the program, one event per line:
+
+        # comment
+        30 down 5
+        36 up 5
+
+    applies to the start of the given frame.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct InputScript {
+    events: Vec<InputEvent>,
+}
+
+impl InputScript {
+    pub fn parse(text: &str) -> Result<InputScript, String> {
+        let mut events: Vec<InputEvent> = Vec::new();
+        for (line_index, line) in text.lines().enumerate() {
+            let line: &str = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let error = |message: &str| format!("input script line {}: {}", line_index + 1, message);
+            if fields.len() != 3 {
+                return Err(error("expected <frame> <down|up> <key>"));
+            }
+            let frame: u64 = fields[0].parse().map_err(|_| error("bad frame number"))?;
+            let pressed: bool = match fields[1] {
+                "down" => true,
+                "up" => false,
+                _ => return Err(error("expected down or up")),
+            };
+            let key: u8 = match u8::from_str_radix(fields[2], 16) {
+                Ok(key) if key < 16 => key,
+                _ => return Err(error("key must be a hex digit 0-f")),
+            };
+            events.push(InputEvent { frame, key, pressed });
+        }
+        events.sort_by_key(|event| event.frame);
+        Ok(InputScript { events })
+    }
+
+    pub fn from_events(mut events: Vec<InputEvent>) -> InputScript {
+        events.sort_by_key(|event| event.frame);
+        InputScript { events }
+    }
+
+    pub fn events(&self) -> &[InputEvent] {
+        &self.events
+    }
+}
+
+pub struct HeadlessRunner {
+    cpu: Chip8,
+    audio: NullAudio,
+    keys: [u8;16],
+    script: InputScript,
+    next_event: usize,
+    conditions: Vec<StopCondition>,
+    watch_hits: Vec<WatchHit>,
+}
+
+impl HeadlessRunner {
+
+    /*
+        Takes a cpu that already has its program loaded
+    */
+    pub fn new(cpu: Chip8) -> HeadlessRunner {
+        HeadlessRunner {
+            cpu,
+            audio: NullAudio::new(),
+            keys: [0;16],
+            script: InputScript::default(),
+            next_event: 0,
+            conditions: Vec::new(),
+            watch_hits: Vec::new(),
+        }
+    }
+
+    pub fn set_input(&mut self, script: InputScript) {
+        self.script = script;
+        self.next_event = 0;
+    }
+
+    pub fn add_stop_condition(&mut self, condition: StopCondition) {
+        self.conditions.push(condition);
+    }
+
+    pub fn cpu(&self) -> &Chip8 {
+        &self.cpu
+    }
+
+    pub fn cpu_mut(&mut self) -> &mut Chip8 {
+        &mut self.cpu
+    }
+
+    pub fn audio(&self) -> &NullAudio {
+        &self.audio
+    }
+
+    /*
+        Accesses caught by the cpu's watchpoints during the run, oldest first
+    */
+    pub fn watch_hits(&self) -> &[WatchHit] {
+        &self.watch_hits
+    }
+
+    /*
+        Run until one of the stop conditions holds, the program exits or fails.
+        Without a frame limit or timeout a program that never stops runs forever.
+    */
+    pub fn run(&mut self) -> StopReason {
+        let started: Instant = Instant::now();
+
+        loop {
+            for condition in &self.conditions {
+                match *condition {
+                    StopCondition::Frames(frames) if self.cpu.get_frame_count() >= frames => {
+                        return StopReason::FrameLimit;
+                    }
+                    StopCondition::Timeout(timeout) if started.elapsed() >= timeout => {
+                        return StopReason::Timeout;
+                    }
+                    _ => {}
+                }
+            }
+
+            self.apply_input();
+
+            let cycles: u32 = self.cpu.start_frame();
+            for _ in 0..cycles {
+                if self.cpu.has_exited() {
+                    return StopReason::Exited;
+                }
+                if let Some(reason) = self.check_before_cycle() {
+                    return reason;
+                }
+
+                let pc_before: u16 = self.cpu.get_pc();
+                if let Err(e) = self.cpu.emulate_cycle() {
+                    return StopReason::Error(e);
+                }
+                self.watch_hits.append(&mut self.cpu.watchpoints_mut().take_hits());
+                if let Some(index) = self.cpu.watchpoints_mut().take_break() {
+                    return StopReason::Watchpoint(index);
+                }
+                let jumped_to_self: bool = matches!(decode(self.cpu.get_opcode()), Some(Instruction::Jump { .. }));
+                if jumped_to_self && self.cpu.get_pc() == pc_before && self.conditions.contains(&StopCondition::PcLoop) {
+                    return StopReason::PcLoop(pc_before);
+                }
+            }
+            self.cpu.end_frame();
+            self.audio.set_pattern(self.cpu.get_frame_count(), self.cpu.get_sound_pattern());
+            self.audio.set_playing(self.cpu.get_frame_count(), self.cpu.is_sound_playing());
+        }
+    }
+
+    fn check_before_cycle(&self) -> Option<StopReason> {
+        let pc: usize = self.cpu.get_pc() as usize;
+        for condition in &self.conditions {
+            if let StopCondition::Opcode(opcode) = *condition {
+                let memory: &[u8] = self.cpu.get_memory();
+                if pc + 1 < memory.len() && ((memory[pc] as u16) << 8 | memory[pc + 1] as u16) == opcode {
+                    return Some(StopReason::Opcode(opcode));
+                }
+            }
+        }
+        None
+    }
+
+    fn apply_input(&mut self) {
+        let frame: u64 = self.cpu.get_frame_count();
+        let events: &[InputEvent] = self.script.events();
+        while self.next_event < events.len() && events[self.next_event].frame <= frame {
+            let event: InputEvent = events[self.next_event];
+            self.keys[event.key as usize] = if event.pressed { 1 } else { 0 };
+            self.next_event += 1;
+        }
+        self.cpu.update_keystate(&self.keys);
+    }
+
+    /*
+        Framebuffer as lines of '#' and '.', one character per pixel.
+        XO-CHIP pixels show the bitplanes set as a hex digit instead.
+    */
+    pub fn framebuffer_text(&self) -> String {
+        let (width, height) = self.cpu.get_resolution();
+        let gfx: &[u8] = self.cpu.get_gfx();
+        let mut out = String::with_capacity((width + 1) * height);
+        for y_coord in 0..height {
+            for x_coord in 0..width {
+                out.push(match gfx[x_coord + y_coord * width] {
+                    0 => '.',
+                    1 => '#',
+                    planes => std::char::from_digit(planes as u32, 16).unwrap_or('?'),
+                });
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /*
+        Framebuffer as a greyscale PNG, each pixel scaled up to a scale x scale square
+    */
+    pub fn framebuffer_png(&self, scale: usize) -> Vec<u8> {
+        let (width, height) = self.cpu.get_resolution();
+        let gfx: &[u8] = self.cpu.get_gfx();
+        let scale: usize = scale.max(1);
+
+        // one filter byte (0, none) in front of every row
+        let mut raw: Vec<u8> = Vec::with_capacity((width * scale + 1) * height * scale);
+        for y_coord in 0..height * scale {
+            raw.push(0);
+            for x_coord in 0..width * scale {
+                raw.push(GREY_LEVELS[(gfx[x_coord / scale + (y_coord / scale) * width] & 0x0F) as usize]);
+            }
+        }
+
+        let mut header: Vec<u8> = Vec::with_capacity(13);
+        header.extend_from_slice(&((width * scale) as u32).to_be_bytes());
+        header.extend_from_slice(&((height * scale) as u32).to_be_bytes());
+        // 8 bit greyscale, default compression/filter, no interlace
+        header.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+        let mut png: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        write_png_chunk(&mut png, b"IHDR", &header);
+        write_png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        write_png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /*
+        Registers, timers and how the run ended as a JSON object
+    */
+    pub fn registers_json(&self, reason: &StopReason) -> String {
+        let cpu: &Chip8 = &self.cpu;
+        let v: Vec<String> = cpu.get_v().iter().map(|value| value.to_string()).collect();
+        let stack: Vec<String> = cpu.get_stack()[..cpu.get_sp() as usize].iter().map(|value| value.to_string()).collect();
+        let sound: Vec<String> = self.audio.timeline().iter()
+            .map(|(frame, playing)| format!("[{}, {}]", frame, playing))
+            .collect();
+        format!(
+            "{{\n  \"stop_reason\": \"{}\",\n  \"frames\": {},\n  \"pc\": {},\n  \"opcode\": {},\n  \"i\": {},\n  \"sp\": {},\n  \"v\": [{}],\n  \"stack\": [{}],\n  \"delay_timer\": {},\n  \"sound_timer\": {},\n  \"sound_timeline\": [{}]\n}}\n",
+            escape_json(&reason.to_string()),
+            cpu.get_frame_count(),
+            cpu.get_pc(),
+            cpu.get_opcode(),
+            cpu.get_i(),
+            cpu.get_sp(),
+            v.join(", "),
+            stack.join(", "),
+            cpu.get_delay_timer(),
+            cpu.get_sound_timer(),
+            sound.join(", "),
+        )
+    }
+}
+
+// background, plane 1, plane 2, both planes, then the four plane combinations
+const GREY_LEVELS: [u8;16] = [0, 255, 170, 85, 220, 200, 180, 160, 140, 120, 100, 80, 60, 50, 40, 30];
+
+fn escape_json(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8;4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start: usize = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc: u32 = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/*
+    zlib stream made of uncompressed deflate blocks, the framebuffers are tiny
+    so compression isn't worth a dependency
+*/
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = vec![0x78, 0x01];
+    let chunks: Vec<&[u8]> = if data.is_empty() { vec![data] } else { data.chunks(0xFFFF).collect() };
+    for (index, chunk) in chunks.iter().enumerate() {
+        let last: u8 = if index == chunks.len() - 1 { 1 } else { 0 };
+        out.push(last);
+        out.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(chunk.len() as u16)).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFF_FFFF;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
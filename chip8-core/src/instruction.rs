@@ -0,0 +1,262 @@
+/*
+    Decoded CHIP-8, SUPER-CHIP and XO-CHIP instructions. x and y name the
+    registers in the opcode, nn is a byte, nnn an address and n a nibble.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    // 0NNN, machine code routine on the original hardware
+    Sys { nnn: u16 },
+    // 00E0
+    Clear,
+    // 00EE
+    Return,
+    // 00CN (SCHIP)
+    ScrollDown { n: u8 },
+    // 00FB (SCHIP)
+    ScrollRight,
+    // 00FC (SCHIP)
+    ScrollLeft,
+    // 00FD (SCHIP)
+    Exit,
+    // 00FE (SCHIP)
+    Lores,
+    // 00FF (SCHIP)
+    Hires,
+    // 1NNN
+    Jump { nnn: u16 },
+    // 2NNN
+    Call { nnn: u16 },
+    // 3XNN
+    SkipIfEqual { x: u8, nn: u8 },
+    // 4XNN
+    SkipIfNotEqual { x: u8, nn: u8 },
+    // 5XY0
+    SkipIfRegistersEqual { x: u8, y: u8 },
+    // 5XY2 (XO-CHIP)
+    SaveRange { x: u8, y: u8 },
+    // 5XY3 (XO-CHIP)
+    LoadRange { x: u8, y: u8 },
+    // 6XNN
+    Set { x: u8, nn: u8 },
+    // 7XNN
+    AddImmediate { x: u8, nn: u8 },
+    // 8XY0
+    Copy { x: u8, y: u8 },
+    // 8XY1
+    Or { x: u8, y: u8 },
+    // 8XY2
+    And { x: u8, y: u8 },
+    // 8XY3
+    Xor { x: u8, y: u8 },
+    // 8XY4
+    Add { x: u8, y: u8 },
+    // 8XY5
+    Subtract { x: u8, y: u8 },
+    // 8XY6
+    ShiftRight { x: u8, y: u8 },
+    // 8XY7
+    SubtractReversed { x: u8, y: u8 },
+    // 8XYE
+    ShiftLeft { x: u8, y: u8 },
+    // 9XY0
+    SkipIfRegistersNotEqual { x: u8, y: u8 },
+    // ANNN
+    SetIndex { nnn: u16 },
+    // BNNN, BXNN with the jump_uses_vx quirk
+    JumpOffset { nnn: u16 },
+    // CXNN
+    Random { x: u8, nn: u8 },
+    // DXYN, DXY0 draws a 16x16 sprite
+    Draw { x: u8, y: u8, n: u8 },
+    // EX9E
+    SkipIfKey { x: u8 },
+    // EXA1
+    SkipIfNotKey { x: u8 },
+    // F000 NNNN (XO-CHIP), the address is the next two bytes
+    SetIndexLong,
+    // FN01 (XO-CHIP)
+    SelectPlanes { n: u8 },
+    // F002 (XO-CHIP)
+    LoadAudio,
+    // FX07
+    GetDelay { x: u8 },
+    // FX0A
+    WaitKey { x: u8 },
+    // FX15
+    SetDelay { x: u8 },
+    // FX18
+    SetSound { x: u8 },
+    // FX1E
+    AddIndex { x: u8 },
+    // FX29
+    FontCharacter { x: u8 },
+    // FX30 (SCHIP)
+    BigFontCharacter { x: u8 },
+    // FX33
+    Bcd { x: u8 },
+    // FX3A (XO-CHIP)
+    SetPitch { x: u8 },
+    // FX55
+    Store { x: u8 },
+    // FX65
+    Load { x: u8 },
+    // FX75 (SCHIP)
+    SaveFlags { x: u8 },
+    // FX85 (SCHIP)
+    LoadFlags { x: u8 },
+}
+
+impl Instruction {
+
+    // bytes taken in memory, F000 carries its address in a second word
+    pub fn length(&self) -> usize {
+        match self {
+            Instruction::SetIndexLong => 4,
+            _ => 2,
+        }
+    }
+}
+
+/*
+    Decode an opcode, None if it is not an instruction of any supported variant
+*/
+pub fn decode(opcode: u16) -> Option<Instruction> {
+    let x: u8 = ((opcode & 0x0F00) >> 8) as u8;
+    let y: u8 = ((opcode & 0x00F0) >> 4) as u8;
+    let n: u8 = (opcode & 0x000F) as u8;
+    let nn: u8 = (opcode & 0x00FF) as u8;
+    let nnn: u16 = opcode & 0x0FFF;
+
+    let instruction: Instruction = match opcode & 0xF000 {
+        0x0000 => match opcode {
+            0x00E0 => Instruction::Clear,
+            0x00EE => Instruction::Return,
+            0x00C0..=0x00CF => Instruction::ScrollDown { n },
+            0x00FB => Instruction::ScrollRight,
+            0x00FC => Instruction::ScrollLeft,
+            0x00FD => Instruction::Exit,
+            0x00FE => Instruction::Lores,
+            0x00FF => Instruction::Hires,
+            _ => Instruction::Sys { nnn },
+        },
+        0x1000 => Instruction::Jump { nnn },
+        0x2000 => Instruction::Call { nnn },
+        0x3000 => Instruction::SkipIfEqual { x, nn },
+        0x4000 => Instruction::SkipIfNotEqual { x, nn },
+        0x5000 => match n {
+            0x0 => Instruction::SkipIfRegistersEqual { x, y },
+            0x2 => Instruction::SaveRange { x, y },
+            0x3 => Instruction::LoadRange { x, y },
+            _ => return None,
+        },
+        0x6000 => Instruction::Set { x, nn },
+        0x7000 => Instruction::AddImmediate { x, nn },
+        0x8000 => match n {
+            0x0 => Instruction::Copy { x, y },
+            0x1 => Instruction::Or { x, y },
+            0x2 => Instruction::And { x, y },
+            0x3 => Instruction::Xor { x, y },
+            0x4 => Instruction::Add { x, y },
+            0x5 => Instruction::Subtract { x, y },
+            0x6 => Instruction::ShiftRight { x, y },
+            0x7 => Instruction::SubtractReversed { x, y },
+            0xE => Instruction::ShiftLeft { x, y },
+            _ => return None,
+        },
+        0x9000 => match n {
+            0x0 => Instruction::SkipIfRegistersNotEqual { x, y },
+            _ => return None,
+        },
+        0xA000 => Instruction::SetIndex { nnn },
+        0xB000 => Instruction::JumpOffset { nnn },
+        0xC000 => Instruction::Random { x, nn },
+        0xD000 => Instruction::Draw { x, y, n },
+        0xE000 => match nn {
+            0x9E => Instruction::SkipIfKey { x },
+            0xA1 => Instruction::SkipIfNotKey { x },
+            _ => return None,
+        },
+        _ => match nn {
+            0x00 if opcode == 0xF000 => Instruction::SetIndexLong,
+            0x01 => Instruction::SelectPlanes { n: x },
+            0x02 if opcode == 0xF002 => Instruction::LoadAudio,
+            0x07 => Instruction::GetDelay { x },
+            0x0A => Instruction::WaitKey { x },
+            0x15 => Instruction::SetDelay { x },
+            0x18 => Instruction::SetSound { x },
+            0x1E => Instruction::AddIndex { x },
+            0x29 => Instruction::FontCharacter { x },
+            0x30 => Instruction::BigFontCharacter { x },
+            0x33 => Instruction::Bcd { x },
+            0x3A => Instruction::SetPitch { x },
+            0x55 => Instruction::Store { x },
+            0x65 => Instruction::Load { x },
+            0x75 => Instruction::SaveFlags { x },
+            0x85 => Instruction::LoadFlags { x },
+            _ => return None,
+        },
+    };
+    Some(instruction)
+}
+
+/*
+    Opcode for an instruction, the inverse of decode. Operands are masked to
+    their field width. F000 only yields its first word, the address follows.
+*/
+pub fn encode(instruction: Instruction) -> u16 {
+    let xy = |opcode: u16, x: u8, y: u8| opcode | ((x as u16 & 0xF) << 8) | ((y as u16 & 0xF) << 4);
+    let xnn = |opcode: u16, x: u8, nn: u8| opcode | ((x as u16 & 0xF) << 8) | nn as u16;
+    let fx = |x: u8, low: u16| 0xF000 | ((x as u16 & 0xF) << 8) | low;
+    match instruction {
+        Instruction::Sys { nnn } => nnn & 0x0FFF,
+        Instruction::Clear => 0x00E0,
+        Instruction::Return => 0x00EE,
+        Instruction::ScrollDown { n } => 0x00C0 | (n as u16 & 0xF),
+        Instruction::ScrollRight => 0x00FB,
+        Instruction::ScrollLeft => 0x00FC,
+        Instruction::Exit => 0x00FD,
+        Instruction::Lores => 0x00FE,
+        Instruction::Hires => 0x00FF,
+        Instruction::Jump { nnn } => 0x1000 | (nnn & 0x0FFF),
+        Instruction::Call { nnn } => 0x2000 | (nnn & 0x0FFF),
+        Instruction::SkipIfEqual { x, nn } => xnn(0x3000, x, nn),
+        Instruction::SkipIfNotEqual { x, nn } => xnn(0x4000, x, nn),
+        Instruction::SkipIfRegistersEqual { x, y } => xy(0x5000, x, y),
+        Instruction::SaveRange { x, y } => xy(0x5002, x, y),
+        Instruction::LoadRange { x, y } => xy(0x5003, x, y),
+        Instruction::Set { x, nn } => xnn(0x6000, x, nn),
+        Instruction::AddImmediate { x, nn } => xnn(0x7000, x, nn),
+        Instruction::Copy { x, y } => xy(0x8000, x, y),
+        Instruction::Or { x, y } => xy(0x8001, x, y),
+        Instruction::And { x, y } => xy(0x8002, x, y),
+        Instruction::Xor { x, y } => xy(0x8003, x, y),
+        Instruction::Add { x, y } => xy(0x8004, x, y),
+        Instruction::Subtract { x, y } => xy(0x8005, x, y),
+        Instruction::ShiftRight { x, y } => xy(0x8006, x, y),
+        Instruction::SubtractReversed { x, y } => xy(0x8007, x, y),
+        Instruction::ShiftLeft { x, y } => xy(0x800E, x, y),
+        Instruction::SkipIfRegistersNotEqual { x, y } => xy(0x9000, x, y),
+        Instruction::SetIndex { nnn } => 0xA000 | (nnn & 0x0FFF),
+        Instruction::JumpOffset { nnn } => 0xB000 | (nnn & 0x0FFF),
+        Instruction::Random { x, nn } => xnn(0xC000, x, nn),
+        Instruction::Draw { x, y, n } => xy(0xD000, x, y) | (n as u16 & 0xF),
+        Instruction::SkipIfKey { x } => xnn(0xE000, x, 0x9E),
+        Instruction::SkipIfNotKey { x } => xnn(0xE000, x, 0xA1),
+        Instruction::SetIndexLong => 0xF000,
+        Instruction::SelectPlanes { n } => fx(n, 0x01),
+        Instruction::LoadAudio => 0xF002,
+        Instruction::GetDelay { x } => fx(x, 0x07),
+        Instruction::WaitKey { x } => fx(x, 0x0A),
+        Instruction::SetDelay { x } => fx(x, 0x15),
+        Instruction::SetSound { x } => fx(x, 0x18),
+        Instruction::AddIndex { x } => fx(x, 0x1E),
+        Instruction::FontCharacter { x } => fx(x, 0x29),
+        Instruction::BigFontCharacter { x } => fx(x, 0x30),
+        Instruction::Bcd { x } => fx(x, 0x33),
+        Instruction::SetPitch { x } => fx(x, 0x3A),
+        Instruction::Store { x } => fx(x, 0x55),
+        Instruction::Load { x } => fx(x, 0x65),
+        Instruction::SaveFlags { x } => fx(x, 0x75),
+        Instruction::LoadFlags { x } => fx(x, 0x85),
+    }
+}
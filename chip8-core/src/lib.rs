@@ -0,0 +1,23 @@
+/*
+    CHIP-8, SUPER-CHIP and XO-CHIP interpreter core. Has no windowing or audio
+    dependencies so debuggers, test harnesses and other frontends can embed it;
+    the SDL frontend lives in the chip_8_emulator crate at the workspace root.
+*/
+
+pub mod assembler;
+pub mod audio;
+pub mod chip8;
+pub mod debugger;
+pub mod disassembler;
+pub mod error;
+pub mod gdb;
+pub mod headless;
+pub mod instruction;
+pub mod movie;
+pub mod quirks;
+pub mod random;
+pub mod rewind;
+pub mod savestate;
+pub mod trace;
+pub mod trace_diff;
+pub mod watch;
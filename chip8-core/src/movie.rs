@@ -0,0 +1,228 @@
+use crate::chip8::{Chip8, Mode, KEY_SIZE};
+use crate::error::Chip8Error;
+use crate::headless::{InputEvent, InputScript};
+use crate::quirks::Quirks;
+use crate::random::RandomKind;
+use crate::savestate::{StateReader, StateWriter};
+
+/*
+    Input recordings ("movies"). Given the same rom, settings and seed the
+    cpu is deterministic, so a run is reproduced exactly by replaying the
+    keypad state it saw at the start of every frame. Only the frames where
+    the keys changed are stored.
+
+    Layout (all integers big endian):
+        magic        4 bytes  "C8MV"
+        version      u16
+        rom hash     u64      see savestate::rom_hash
+        mode         u8
+        quirks       u8       see Quirks::to_bits
+        speed        u32      instructions per second
+        rng          u8       RandomKind, 0xFF for a custom source
+        seed         u64
+        length       u64      frames recorded
+        changes      u32      number of records that follow
+        records      frame u64, keys u16 (bit n set while key n is down)
+*/
+
+pub const MAGIC: [u8;4] = *b"C8MV";
+pub const VERSION: u16 = 1;
+const HEADER_SIZE: usize = 4 + 2 + 8 + 1 + 1 + 4 + 1 + 8 + 8 + 4;
+const RECORD_SIZE: usize = 8 + 2;
+const CUSTOM_RANDOM: u8 = 0xFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KeyChange {
+    frame: u64,
+    keys: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct Movie {
+    pub rom_hash: u64,
+    pub mode: Mode,
+    pub quirks: Quirks,
+    pub speed: u32,
+    pub random_kind: Option<RandomKind>,
+    pub seed: u64,
+    length: u64,
+    changes: Vec<KeyChange>,
+}
+
+impl Movie {
+
+    /*
+        Start recording a cpu that has its program loaded and has not run yet
+    */
+    pub fn new(cpu: &Chip8) -> Movie {
+        Movie {
+            rom_hash: cpu.get_rom_hash(),
+            mode: cpu.mode(),
+            quirks: cpu.quirks(),
+            speed: cpu.get_speed(),
+            random_kind: cpu.get_random_kind(),
+            seed: cpu.get_seed(),
+            length: cpu.get_frame_count(),
+            changes: Vec::new(),
+        }
+    }
+
+    /*
+        Record the keys given to the cpu before running the given frame. Anything
+        recorded from that frame on is replaced, so after rewinding or loading an
+        earlier state of the same run the recording simply continues from there.
+    */
+    pub fn record(&mut self, frame: u64, keys: &[u8;KEY_SIZE]) {
+        self.truncate(frame);
+        let packed: u16 = pack_keys(keys);
+        if packed != self.packed_keys_at(frame) {
+            self.changes.push(KeyChange { frame, keys: packed });
+        }
+        self.length = frame + 1;
+    }
+
+    // forget everything from the given frame on
+    pub fn truncate(&mut self, frame: u64) {
+        self.changes.retain(|change| change.frame < frame);
+        self.length = self.length.min(frame);
+    }
+
+    // number of frames covered by the recording
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /*
+        Keypad state to feed the cpu before running the given frame
+    */
+    pub fn keys_at(&self, frame: u64) -> [u8;KEY_SIZE] {
+        unpack_keys(self.packed_keys_at(frame))
+    }
+
+    fn packed_keys_at(&self, frame: u64) -> u16 {
+        // changes are kept in frame order, the last one not after the frame is in effect
+        let count: usize = self.changes.partition_point(|change| change.frame <= frame);
+        match count {
+            0 => 0,
+            _ => self.changes[count - 1].keys,
+        }
+    }
+
+    /*
+        The recording as key down/up events, for the headless runner
+    */
+    pub fn input_script(&self) -> InputScript {
+        let mut events: Vec<InputEvent> = Vec::new();
+        let mut previous: u16 = 0;
+        for change in &self.changes {
+            for key in 0..KEY_SIZE as u8 {
+                let pressed: bool = change.keys & (1 << key) != 0;
+                if pressed != (previous & (1 << key) != 0) {
+                    events.push(InputEvent { frame: change.frame, key, pressed });
+                }
+            }
+            previous = change.keys;
+        }
+        InputScript::from_events(events)
+    }
+
+    /*
+        Put the cpu in the recorded configuration, before initializing it and loading the rom
+    */
+    pub fn configure(&self, cpu: &mut Chip8) {
+        cpu.set_mode(self.mode);
+        cpu.set_quirks(self.quirks);
+        cpu.set_speed(self.speed);
+        cpu.seed_rng(self.seed);
+        if let Some(kind) = self.random_kind {
+            cpu.set_random_kind(kind);
+        }
+    }
+
+    /*
+        Make sure the cpu was loaded with the rom the movie was recorded on
+    */
+    pub fn check_rom(&self, cpu: &Chip8) -> Result<(), Chip8Error> {
+        if cpu.get_rom_hash() != self.rom_hash {
+            return Err(bad_movie("recorded with a different rom"));
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.bytes(&MAGIC);
+        writer.u16(VERSION);
+        writer.u64(self.rom_hash);
+        writer.u8(self.mode.to_id());
+        writer.u8(self.quirks.to_bits());
+        writer.u32(self.speed);
+        writer.u8(self.random_kind.map_or(CUSTOM_RANDOM, RandomKind::to_id));
+        writer.u64(self.seed);
+        writer.u64(self.length);
+        writer.u32(self.changes.len() as u32);
+        for change in &self.changes {
+            writer.u64(change.frame);
+            writer.u16(change.keys);
+        }
+        writer.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Movie, Chip8Error> {
+        if bytes.len() < HEADER_SIZE || bytes[0..4] != MAGIC {
+            return Err(bad_movie("not a movie file"));
+        }
+        let mut reader = StateReader::new(&bytes[4..]);
+        if reader.u16()? != VERSION {
+            return Err(bad_movie("unsupported movie version"));
+        }
+        let rom_hash: u64 = reader.u64()?;
+        let mode: Mode = Mode::from_id(reader.u8()?).ok_or_else(|| bad_movie("unknown mode"))?;
+        let quirks: Quirks = Quirks::from_bits(reader.u8()?).ok_or_else(|| bad_movie("unknown quirks"))?;
+        let speed: u32 = reader.u32()?;
+        let random_kind: Option<RandomKind> = match reader.u8()? {
+            CUSTOM_RANDOM => None,
+            id => Some(RandomKind::from_id(id).ok_or_else(|| bad_movie("unknown random generator"))?),
+        };
+        let seed: u64 = reader.u64()?;
+        let length: u64 = reader.u64()?;
+        let count: usize = reader.u32()? as usize;
+
+        // check the size up front so a truncated file can't be half read
+        if bytes.len() != HEADER_SIZE + count * RECORD_SIZE {
+            return Err(bad_movie("wrong size for its record count"));
+        }
+        let mut changes: Vec<KeyChange> = Vec::with_capacity(count);
+        for _ in 0..count {
+            let change = KeyChange { frame: reader.u64()?, keys: reader.u16()? };
+            if changes.last().is_some_and(|last: &KeyChange| last.frame >= change.frame) || change.frame >= length {
+                return Err(bad_movie("records out of order"));
+            }
+            changes.push(change);
+        }
+
+        Ok(Movie { rom_hash, mode, quirks, speed, random_kind, seed, length, changes })
+    }
+}
+
+fn pack_keys(keys: &[u8;KEY_SIZE]) -> u16 {
+    let mut packed: u16 = 0;
+    for (key, state) in keys.iter().enumerate() {
+        if *state != 0 {
+            packed |= 1 << key;
+        }
+    }
+    packed
+}
+
+fn unpack_keys(packed: u16) -> [u8;KEY_SIZE] {
+    let mut keys = [0;KEY_SIZE];
+    for (key, state) in keys.iter_mut().enumerate() {
+        *state = ((packed >> key) & 1) as u8;
+    }
+    keys
+}
+
+fn bad_movie(reason: &str) -> Chip8Error {
+    Chip8Error::BadMovie { reason: String::from(reason) }
+}
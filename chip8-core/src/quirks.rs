@@ -0,0 +1,131 @@
+/*
+    Behaviour of the instructions that differ between CHIP-8 interpreters.
+    ROMs written for one interpreter often rely on its particular answers,
+    so the cpu takes one of these profiles instead of hardcoding a choice.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    // 8XY6/8XYE shift VY into VX instead of shifting VX in place
+    pub shift_uses_vy: bool,
+    // how far FX55/FX65 move I after storing or loading registers
+    pub load_store_increment: IndexIncrement,
+    // BNNN is treated as BXNN and jumps to XNN plus VX instead of NNN plus V0
+    pub jump_uses_vx: bool,
+    // 8XY1/8XY2/8XY3 reset VF to 0
+    pub logic_resets_vf: bool,
+    // DXYN wraps pixels past the screen edge to the other side instead of clipping them
+    pub wrap_sprites: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexIncrement {
+    // I is left unmodified
+    None,
+    // I is increased by X
+    X,
+    // I is increased by X + 1, ending just past the last register written
+    XPlusOne,
+}
+
+impl Quirks {
+
+    // The original interpreter on the RCA COSMAC VIP
+    pub const COSMAC_VIP: Quirks = Quirks {
+        shift_uses_vy: true,
+        load_store_increment: IndexIncrement::XPlusOne,
+        jump_uses_vx: false,
+        logic_resets_vf: true,
+        wrap_sprites: false,
+    };
+
+    // CHIP-48 on the HP-48 calculators
+    pub const CHIP_48: Quirks = Quirks {
+        shift_uses_vy: false,
+        load_store_increment: IndexIncrement::X,
+        jump_uses_vx: true,
+        logic_resets_vf: false,
+        wrap_sprites: false,
+    };
+
+    // SUPER-CHIP 1.1
+    pub const SUPER_CHIP: Quirks = Quirks {
+        shift_uses_vy: false,
+        load_store_increment: IndexIncrement::None,
+        jump_uses_vx: true,
+        logic_resets_vf: false,
+        wrap_sprites: false,
+    };
+
+    // XO-CHIP as implemented by Octo
+    pub const XO_CHIP: Quirks = Quirks {
+        shift_uses_vy: true,
+        load_store_increment: IndexIncrement::XPlusOne,
+        jump_uses_vx: false,
+        logic_resets_vf: false,
+        wrap_sprites: true,
+    };
+
+    // What most modern emulators (and this one historically) do
+    pub const MODERN: Quirks = Quirks {
+        shift_uses_vy: false,
+        load_store_increment: IndexIncrement::None,
+        jump_uses_vx: false,
+        logic_resets_vf: false,
+        wrap_sprites: true,
+    };
+
+    /*
+        Look up a preset by name, e.g. "vip" or "schip"
+    */
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        match name.to_ascii_lowercase().as_str() {
+            "vip" | "cosmac" | "cosmac-vip" | "chip8" | "chip-8" => Some(Quirks::COSMAC_VIP),
+            "chip48" | "chip-48" => Some(Quirks::CHIP_48),
+            "schip" | "superchip" | "super-chip" => Some(Quirks::SUPER_CHIP),
+            "xochip" | "xo-chip" | "octo" => Some(Quirks::XO_CHIP),
+            "modern" => Some(Quirks::MODERN),
+            _ => None,
+        }
+    }
+
+    /*
+        Pack into a byte for file headers, one bit per flag and two for the increment
+    */
+    pub fn to_bits(self) -> u8 {
+        let increment: u8 = match self.load_store_increment {
+            IndexIncrement::None => 0,
+            IndexIncrement::X => 1,
+            IndexIncrement::XPlusOne => 2,
+        };
+        self.shift_uses_vy as u8
+            | increment << 1
+            | (self.jump_uses_vx as u8) << 3
+            | (self.logic_resets_vf as u8) << 4
+            | (self.wrap_sprites as u8) << 5
+    }
+
+    pub fn from_bits(bits: u8) -> Option<Quirks> {
+        let load_store_increment: IndexIncrement = match (bits >> 1) & 0b11 {
+            0 => IndexIncrement::None,
+            1 => IndexIncrement::X,
+            2 => IndexIncrement::XPlusOne,
+            _ => return None,
+        };
+        if bits >> 6 != 0 {
+            return None;
+        }
+        Some(Quirks {
+            shift_uses_vy: bits & 1 != 0,
+            load_store_increment,
+            jump_uses_vx: bits & (1 << 3) != 0,
+            logic_resets_vf: bits & (1 << 4) != 0,
+            wrap_sprites: bits & (1 << 5) != 0,
+        })
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Quirks::MODERN
+    }
+}
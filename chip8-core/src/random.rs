@@ -0,0 +1,121 @@
+/*
+    Random number generators for CXNN. The cpu owns one behind this trait so
+    runs can be made repeatable by seeding it, and its state can be stored in
+    save states and restored exactly.
+
+    There is no COSMAC VIP generator. The VIP's CXNN mixed a counter bumped
+    by the display interrupt with bytes of the interpreter's own code, so
+    matching it needs the interpreter image and the VIP's interrupt timing,
+    neither of which this emulator has.
+*/
+pub trait RandomSource {
+    fn next_byte(&mut self) -> u8;
+
+    // whole internal state, what save states store
+    fn state(&self) -> u64;
+
+    fn set_state(&mut self, state: u64);
+}
+
+/*
+    The built in generators, selectable by name
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomKind {
+    Xorshift,
+    Lcg,
+}
+
+impl RandomKind {
+    pub fn from_name(name: &str) -> Option<RandomKind> {
+        match name.to_ascii_lowercase().as_str() {
+            "xorshift" => Some(RandomKind::Xorshift),
+            "lcg" => Some(RandomKind::Lcg),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_id(self) -> u8 {
+        match self {
+            RandomKind::Xorshift => 0,
+            RandomKind::Lcg => 1,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Option<RandomKind> {
+        match id {
+            0 => Some(RandomKind::Xorshift),
+            1 => Some(RandomKind::Lcg),
+            _ => None,
+        }
+    }
+
+    pub fn create(self, seed: u64) -> Box<dyn RandomSource> {
+        match self {
+            RandomKind::Xorshift => Box::new(Xorshift::new(seed)),
+            RandomKind::Lcg => Box::new(Lcg::new(seed)),
+        }
+    }
+}
+
+/*
+    Marsaglia's xorshift64, fast with good statistical quality. The default.
+*/
+pub struct Xorshift {
+    state: u64,
+}
+
+impl Xorshift {
+    pub fn new(seed: u64) -> Xorshift {
+        let mut generator = Xorshift { state: 0 };
+        generator.set_state(seed);
+        generator
+    }
+}
+
+impl RandomSource for Xorshift {
+    fn next_byte(&mut self) -> u8 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 32) as u8
+    }
+
+    fn state(&self) -> u64 {
+        self.state
+    }
+
+    fn set_state(&mut self, state: u64) {
+        // xorshift gets stuck at zero, map it to an arbitrary non-zero state
+        self.state = if state == 0 { 0x9E37_79B9_7F4A_7C15 } else { state };
+    }
+}
+
+/*
+    The linear congruential generator from the C standard's example rand(),
+    what many early PC interpreters ended up using
+*/
+pub struct Lcg {
+    state: u32,
+}
+
+impl Lcg {
+    pub fn new(seed: u64) -> Lcg {
+        Lcg { state: seed as u32 }
+    }
+}
+
+impl RandomSource for Lcg {
+    fn next_byte(&mut self) -> u8 {
+        self.state = self.state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (self.state >> 16) as u8
+    }
+
+    fn state(&self) -> u64 {
+        self.state as u64
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.state = state as u32;
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+
+/*
+    Bounded history of save states for rewinding, one entry per frame.
+
+    Only the newest state is kept whole. Every older one is stored as the XOR
+    of itself with the state after it, run length encoded; consecutive frames
+    differ in a handful of bytes so these deltas are tiny. XOR works both ways,
+    so stepping back from the newest state just undoes one delta at a time, and
+    the oldest delta can be dropped when the buffer is full without touching
+    the rest.
+*/
+pub struct RewindBuffer {
+    capacity: usize,
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+}
+
+impl RewindBuffer {
+
+    /*
+        Keep at most capacity states (frames)
+    */
+    pub fn new(capacity: usize) -> RewindBuffer {
+        RewindBuffer {
+            capacity,
+            latest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    pub fn with_seconds(seconds: f32) -> RewindBuffer {
+        RewindBuffer::new((seconds.max(0.0) * 60.0) as usize)
+    }
+
+    /*
+        Record the state of a new frame
+    */
+    pub fn push(&mut self, state: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(latest) = self.latest.take() {
+            if latest.len() == state.len() {
+                self.deltas.push_back(encode_delta(&latest, &state));
+            } else {
+                // a state of another shape (mode switch) can't be diffed against
+                self.deltas.clear();
+            }
+        }
+        self.latest = Some(state);
+        while self.deltas.len() + 1 > self.capacity {
+            self.deltas.pop_front();
+        }
+    }
+
+    /*
+        Step back a frame. The newest state is the frame already on screen, so
+        it is dropped and the one before it returned; that one stays as the
+        newest since it is on screen once loaded. None when there is nothing
+        older to go back to.
+    */
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let delta: Vec<u8> = self.deltas.pop_back()?;
+        let previous: &mut Vec<u8> = self.latest.as_mut()?;
+        apply_delta(previous, &delta);
+        Some(previous.clone())
+    }
+
+    pub fn len(&self) -> usize {
+        match self.latest {
+            Some(_) => self.deltas.len() + 1,
+            None => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    // bytes held by the stored states, for showing how much history costs
+    pub fn memory_usage(&self) -> usize {
+        let latest: usize = self.latest.as_ref().map_or(0, |state| state.len());
+        latest + self.deltas.iter().map(|delta| delta.len()).sum::<usize>()
+    }
+}
+
+/*
+    XOR of two equally long states as (zero run: u16, literal count: u16, literals) records
+*/
+fn encode_delta(older: &[u8], newer: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::new();
+    let mut position: usize = 0;
+    while position < older.len() {
+        let mut zeros: usize = 0;
+        while position < older.len() && older[position] == newer[position] && zeros < u16::MAX as usize {
+            zeros += 1;
+            position += 1;
+        }
+        let literal_start: usize = position;
+        while position < older.len() && older[position] != newer[position] && position - literal_start < u16::MAX as usize {
+            position += 1;
+        }
+        out.extend_from_slice(&(zeros as u16).to_le_bytes());
+        out.extend_from_slice(&((position - literal_start) as u16).to_le_bytes());
+        for index in literal_start..position {
+            out.push(older[index] ^ newer[index]);
+        }
+    }
+    out
+}
+
+fn apply_delta(state: &mut [u8], delta: &[u8]) {
+    let mut position: usize = 0;
+    let mut cursor: usize = 0;
+    while cursor + 4 <= delta.len() {
+        let zeros: usize = u16::from_le_bytes([delta[cursor], delta[cursor + 1]]) as usize;
+        let literals: usize = u16::from_le_bytes([delta[cursor + 2], delta[cursor + 3]]) as usize;
+        cursor += 4;
+        position += zeros;
+        for offset in 0..literals {
+            state[position + offset] ^= delta[cursor + offset];
+        }
+        position += literals;
+        cursor += literals;
+    }
+}
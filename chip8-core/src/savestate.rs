@@ -0,0 +1,125 @@
+use crate::error::Chip8Error;
+
+/*
+    Byte level helpers for the save state format written by Chip8::save_state.
+
+    Layout (all integers big endian):
+        magic        4 bytes  "C8SV"
+        version      u16
+        rom hash     u64      FNV-1a of the loaded rom, see rom_hash
+        mode         u8
+        body         machine state, field order defined by Chip8::save_state
+
+    The version is bumped whenever the body changes, old states are rejected
+    rather than misread.
+*/
+
+pub const MAGIC: [u8;4] = *b"C8SV";
+pub const VERSION: u16 = 2;
+pub const HEADER_SIZE: usize = 4 + 2 + 8 + 1;
+
+/*
+    64 bit FNV-1a, enough to tell roms apart without pulling in a hashing crate
+*/
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in rom {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+#[derive(Default)]
+pub struct StateWriter {
+    bytes: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> StateWriter {
+        StateWriter::default()
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn bytes(&mut self, values: &[u8]) {
+        self.bytes.extend_from_slice(values);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+pub struct StateReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> StateReader<'a> {
+        StateReader { bytes, position: 0 }
+    }
+
+    pub fn bytes(&mut self, count: usize) -> Result<&'a [u8], Chip8Error> {
+        if self.bytes.len() - self.position < count {
+            return Err(bad_state("truncated save state"));
+        }
+        let slice: &'a [u8] = &self.bytes[self.position..self.position + count];
+        self.position += count;
+        Ok(slice)
+    }
+
+    // bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Chip8Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Chip8Error> {
+        let mut buffer = [0;2];
+        buffer.copy_from_slice(self.bytes(2)?);
+        Ok(u16::from_be_bytes(buffer))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Chip8Error> {
+        let mut buffer = [0;4];
+        buffer.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_be_bytes(buffer))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Chip8Error> {
+        let mut buffer = [0;8];
+        buffer.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_be_bytes(buffer))
+    }
+
+    pub fn bool(&mut self) -> Result<bool, Chip8Error> {
+        Ok(self.u8()? != 0)
+    }
+}
+
+pub fn bad_state(reason: &str) -> Chip8Error {
+    Chip8Error::BadSaveState { reason: String::from(reason) }
+}
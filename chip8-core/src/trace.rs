@@ -0,0 +1,353 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::debugger::Register;
+use crate::disassembler::mnemonic;
+use crate::error::Chip8Error;
+use crate::instruction::{decode, Instruction};
+use crate::savestate::{StateReader, StateWriter};
+
+/*
+    Execution traces, one record per instruction the cpu runs: the frame, pc
+    and opcode, the registers the instruction changed and the memory it wrote.
+    They are meant for comparing runs, with each other or with other emulators.
+
+    The text format has a line per record, with hex values:
+
+        frame pc opcode mnemonic [; changes]
+        42 0234 8014 ADD V0, V1 ; V0=2A VF=00
+        42 0236 F233 LD B, V2 ; [0300]=01 [0301]=02 [0302]=08
+
+    Changes list V0-VF, I, SP, DT and ST as NAME=value and written memory as
+    [address]=value, in that order. The pc is not a change, it is the next
+    record's pc.
+
+    The binary format holds the same records, big endian:
+
+        header  "C8TR", version u16
+        record  pc u16, opcode u16, [address u16 after F000], frame u32,
+                change count u8, (register u8, value u16) per change,
+                write count u8, (address u16, value u8) per write
+
+    with registers numbered 0-15 for V0-VF, then 16 I, 17 SP, 18 DT, 19 ST.
+*/
+
+pub const MAGIC: [u8;4] = *b"C8TR";
+pub const VERSION: u16 = 1;
+
+// the registers compared before and after each instruction, in record order
+pub const TRACED_REGISTERS: [Register;20] = [
+    Register::V(0), Register::V(1), Register::V(2), Register::V(3),
+    Register::V(4), Register::V(5), Register::V(6), Register::V(7),
+    Register::V(8), Register::V(9), Register::V(10), Register::V(11),
+    Register::V(12), Register::V(13), Register::V(14), Register::V(15),
+    Register::I, Register::Sp, Register::DelayTimer, Register::SoundTimer,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    Text,
+    Binary,
+}
+
+impl TraceFormat {
+    pub fn from_name(name: &str) -> Option<TraceFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" => Some(TraceFormat::Text),
+            "binary" | "bin" => Some(TraceFormat::Binary),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    pub frame: u64,
+    pub pc: u16,
+    pub opcode: u16,
+    // the address following F000, 0 for every other instruction
+    pub operand: u16,
+    pub changes: Vec<(Register, u16)>,
+    pub writes: Vec<(u16, u8)>,
+}
+
+impl TraceRecord {
+    pub fn instruction(&self) -> Option<Instruction> {
+        decode(self.opcode)
+    }
+
+    pub fn mnemonic(&self) -> String {
+        match self.instruction() {
+            Some(instruction) => mnemonic(instruction, self.operand),
+            None => format!("DW #{:04X}", self.opcode),
+        }
+    }
+
+    /*
+        The record as a line of the text format, without the newline
+    */
+    pub fn to_text(&self) -> String {
+        let mut line: String = format!("{} {:04X} {:04X} {}", self.frame, self.pc, self.opcode, self.mnemonic());
+        if self.changes.is_empty() && self.writes.is_empty() {
+            return line;
+        }
+        line.push_str(" ;");
+        for (register, value) in &self.changes {
+            match register {
+                Register::I => line.push_str(&format!(" I={:04X}", value)),
+                _ => line.push_str(&format!(" {}={:02X}", register_name(*register), value)),
+            }
+        }
+        for (address, value) in &self.writes {
+            line.push_str(&format!(" [{:04X}]={:02X}", address, value));
+        }
+        line
+    }
+
+    pub fn write_binary(&self, writer: &mut StateWriter) {
+        writer.u16(self.pc);
+        writer.u16(self.opcode);
+        if self.opcode == 0xF000 {
+            writer.u16(self.operand);
+        }
+        writer.u32(self.frame as u32);
+        writer.u8(self.changes.len() as u8);
+        for (register, value) in &self.changes {
+            writer.u8(register_id(*register));
+            writer.u16(*value);
+        }
+        writer.u8(self.writes.len() as u8);
+        for (address, value) in &self.writes {
+            writer.u16(*address);
+            writer.u8(*value);
+        }
+    }
+}
+
+// upper case names used by the text format
+pub fn register_name(register: Register) -> String {
+    match register {
+        Register::V(index) => format!("V{:X}", index),
+        Register::I => String::from("I"),
+        Register::Pc => String::from("PC"),
+        Register::Sp => String::from("SP"),
+        Register::DelayTimer => String::from("DT"),
+        Register::SoundTimer => String::from("ST"),
+    }
+}
+
+pub fn register_id(register: Register) -> u8 {
+    match register {
+        Register::V(index) => index,
+        Register::I => 16,
+        Register::Sp => 17,
+        Register::DelayTimer => 18,
+        Register::SoundTimer => 19,
+        Register::Pc => 20,
+    }
+}
+
+/*
+    Records of a trace written in the binary format
+*/
+pub fn read_binary(bytes: &[u8]) -> Result<Vec<TraceRecord>, Chip8Error> {
+    if bytes.len() < 6 || bytes[0..4] != MAGIC {
+        return Err(bad_trace("not a binary trace"));
+    }
+    let mut reader = StateReader::new(&bytes[4..]);
+    let truncated = |_| bad_trace("truncated record");
+    if reader.u16().map_err(truncated)? != VERSION {
+        return Err(bad_trace("unsupported trace version"));
+    }
+
+    let mut records: Vec<TraceRecord> = Vec::new();
+    while reader.remaining() > 0 {
+        let pc: u16 = reader.u16().map_err(truncated)?;
+        let opcode: u16 = reader.u16().map_err(truncated)?;
+        let operand: u16 = if opcode == 0xF000 { reader.u16().map_err(truncated)? } else { 0 };
+        let frame: u64 = reader.u32().map_err(truncated)? as u64;
+        let mut changes: Vec<(Register, u16)> = Vec::new();
+        for _ in 0..reader.u8().map_err(truncated)? {
+            let id: u8 = reader.u8().map_err(truncated)?;
+            let register: Register = *TRACED_REGISTERS.get(id as usize).ok_or_else(|| bad_trace("unknown register"))?;
+            changes.push((register, reader.u16().map_err(truncated)?));
+        }
+        let mut writes: Vec<(u16, u8)> = Vec::new();
+        for _ in 0..reader.u8().map_err(truncated)? {
+            writes.push((reader.u16().map_err(truncated)?, reader.u8().map_err(truncated)?));
+        }
+        records.push(TraceRecord { frame, pc, opcode, operand, changes, writes });
+    }
+    Ok(records)
+}
+
+fn bad_trace(reason: &str) -> Chip8Error {
+    Chip8Error::BadTrace { reason: String::from(reason) }
+}
+
+/*
+    Which instructions make it into the trace. Every part left as None lets
+    everything through.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceFilter {
+    // first and last pc, inclusive
+    pub addresses: Option<(u16, u16)>,
+    // opcode classes by their first hex digit, bit N keeps NXXX
+    pub classes: Option<u16>,
+    // first and last frame, inclusive
+    pub frames: Option<(u64, u64)>,
+}
+
+impl TraceFilter {
+    pub fn matches(&self, frame: u64, pc: u16, opcode: u16) -> bool {
+        self.addresses.is_none_or(|(start, end)| (start..=end).contains(&pc))
+            && self.classes.is_none_or(|classes| classes & (1 << (opcode >> 12)) != 0)
+            && self.frames.is_none_or(|(start, end)| (start..=end).contains(&frame))
+    }
+
+    /*
+        "START-END" in hex, or a single address
+    */
+    pub fn parse_addresses(text: &str) -> Result<(u16, u16), String> {
+        let parse = |part: &str| u16::from_str_radix(part.trim().trim_start_matches("0x"), 16)
+            .map_err(|_| format!("bad address '{}'", part));
+        let (start, end) = match text.split_once('-') {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => (parse(text)?, parse(text)?),
+        };
+        if end < start {
+            return Err(format!("range {} ends before it starts", text));
+        }
+        Ok((start, end))
+    }
+
+    /*
+        Comma separated first hex digits, e.g. "8,d,f" for arithmetic, drawing
+        and the FXNN instructions
+    */
+    pub fn parse_classes(text: &str) -> Result<u16, String> {
+        let mut classes: u16 = 0;
+        for class in text.split(',') {
+            let class: &str = class.trim();
+            match u8::from_str_radix(class, 16) {
+                Ok(digit) if class.len() == 1 => classes |= 1 << digit,
+                _ => return Err(format!("bad opcode class '{}', expected a hex digit 0-F", class)),
+            }
+        }
+        Ok(classes)
+    }
+
+    /*
+        "START-END" in decimal, END may be left out to trace to the end
+    */
+    pub fn parse_frames(text: &str) -> Result<(u64, u64), String> {
+        let parse = |part: &str| part.trim().parse::<u64>().map_err(|_| format!("bad frame '{}'", part));
+        let (start, end) = match text.split_once('-') {
+            Some((start, "")) => (parse(start)?, u64::MAX),
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => (parse(text)?, parse(text)?),
+        };
+        if end < start {
+            return Err(format!("frames {} end before they start", text));
+        }
+        Ok((start, end))
+    }
+}
+
+/*
+    Writes the records of the instructions the filter lets through. The cpu
+    drives it, see Chip8::set_tracer. Write errors stop the trace and are
+    reported by finish.
+*/
+pub struct Tracer {
+    output: Box<dyn Write>,
+    format: TraceFormat,
+    filter: TraceFilter,
+    // the instruction being run, with the registers from before it
+    pending: Option<(TraceRecord, [u16;20])>,
+    records: u64,
+    error: Option<io::Error>,
+}
+
+impl Tracer {
+    pub fn new(mut output: Box<dyn Write>, format: TraceFormat, filter: TraceFilter) -> Tracer {
+        let mut error: Option<io::Error> = None;
+        if format == TraceFormat::Binary {
+            let mut header = StateWriter::new();
+            header.bytes(&MAGIC);
+            header.u16(VERSION);
+            error = output.write_all(&header.finish()).err();
+        }
+        Tracer { output, format, filter, pending: None, records: 0, error }
+    }
+
+    pub fn create(path: &str, format: TraceFormat, filter: TraceFilter) -> io::Result<Tracer> {
+        let file: File = File::create(path)?;
+        Ok(Tracer::new(Box::new(BufWriter::new(file)), format, filter))
+    }
+
+    pub fn filter(&self) -> &TraceFilter {
+        &self.filter
+    }
+
+    // records written so far
+    pub fn records(&self) -> u64 {
+        self.records
+    }
+
+    /*
+        Flush the output, reporting the first write error if there was one
+    */
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.output.flush()
+    }
+
+    pub(crate) fn wants(&self, frame: u64, pc: u16, opcode: u16) -> bool {
+        self.error.is_none() && self.filter.matches(frame, pc, opcode)
+    }
+
+    pub(crate) fn begin(&mut self, frame: u64, pc: u16, opcode: u16, operand: u16, registers: [u16;20]) {
+        let record = TraceRecord { frame, pc, opcode, operand, changes: Vec::new(), writes: Vec::new() };
+        self.pending = Some((record, registers));
+    }
+
+    pub(crate) fn note_write(&mut self, address: usize, value: u8) {
+        if let Some((record, _)) = &mut self.pending {
+            record.writes.push((address as u16, value));
+        }
+    }
+
+    // the instruction failed, nothing to record
+    pub(crate) fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    pub(crate) fn end(&mut self, registers: [u16;20]) {
+        let (mut record, before) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        for (index, register) in TRACED_REGISTERS.iter().enumerate() {
+            if before[index] != registers[index] {
+                record.changes.push((*register, registers[index]));
+            }
+        }
+
+        let result: io::Result<()> = match self.format {
+            TraceFormat::Text => writeln!(self.output, "{}", record.to_text()),
+            TraceFormat::Binary => {
+                let mut writer = StateWriter::new();
+                record.write_binary(&mut writer);
+                self.output.write_all(&writer.finish())
+            }
+        };
+        match result {
+            Ok(()) => self.records += 1,
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use std::fmt;
+
+use crate::debugger::Register;
+use crate::disassembler::mnemonic;
+use crate::error::Chip8Error;
+use crate::instruction::decode;
+use crate::trace::{read_binary, register_id, register_name, TraceRecord, MAGIC, TRACED_REGISTERS};
+
+/*
+    Lines two execution traces up by instruction index and finds the first
+    one where they disagree. One trace is usually ours, the other exported
+    from another emulator, so besides our own text and binary formats (see
+    trace.rs) a looser text format is read:
+
+        frame pc opcode [anything] [; NAME=value ... [address]=value ...]
+        - 0234 8014 add v0, v1 ; V0=2A VF=00 PC=0236
+
+    The frame is decimal or '-' when the emulator doesn't count frames, pc,
+    opcode and values are hex with an optional 0x, # or $. Whatever sits
+    between the opcode and the ';' is ignored, so any mnemonic style works.
+    Register names are V0-VF, I, SP, DT and ST in any case, PC is skipped.
+    Listing every register on every line is fine too: register values are
+    tracked from zero, as after a reset, and compared after each instruction,
+    so a full dump and a list of changes describe the same state. Blank lines
+    and lines starting with '#' are skipped.
+
+    Both traces have to start at the same instruction and be recorded with
+    the same filters, otherwise the indexes don't line up.
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub record: TraceRecord,
+    // false for '-', the frame of the record is then 0
+    pub frame_known: bool,
+    // the line as it was written, for showing context
+    pub text: String,
+}
+
+impl TraceEntry {
+    fn from_record(record: TraceRecord) -> TraceEntry {
+        let text: String = record.to_text();
+        TraceEntry { record, frame_known: true, text }
+    }
+}
+
+// register values in TRACED_REGISTERS order
+pub type Registers = [u16;20];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffOptions {
+    // instructions shown before and after the divergence
+    pub context: usize,
+    pub compare_frames: bool,
+    pub compare_memory: bool,
+}
+
+impl Default for DiffOptions {
+    fn default() -> DiffOptions {
+        DiffOptions { context: 5, compare_frames: true, compare_memory: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    // one trace ran out, the lengths of both
+    Length(usize, usize),
+    Frame(u64, u64),
+    Pc(u16, u16),
+    Opcode(u16, u16),
+    // value after the instruction, ours then the reference's
+    Register(Register, u16, u16),
+    Writes(Vec<(u16, u8)>, Vec<(u16, u8)>),
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difference::Length(ours, reference) => {
+                write!(f, "length: ours has {} instructions, reference {}", ours, reference)
+            }
+            Difference::Frame(ours, reference) => write!(f, "frame: ours {}, reference {}", ours, reference),
+            Difference::Pc(ours, reference) => write!(f, "pc: ours {:04X}, reference {:04X}", ours, reference),
+            Difference::Opcode(ours, reference) => write!(
+                f,
+                "opcode: ours {:04X} ({}), reference {:04X} ({})",
+                ours, describe(*ours), reference, describe(*reference)
+            ),
+            Difference::Register(register, ours, reference) => write!(
+                f,
+                "{}: ours {}, reference {}",
+                register_name(*register), register_value(*register, *ours), register_value(*register, *reference)
+            ),
+            Difference::Writes(ours, reference) => {
+                write!(f, "memory writes: ours {}, reference {}", writes_text(ours), writes_text(reference))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub index: usize,
+    pub differences: Vec<Difference>,
+    // the registers both traces agreed on before the instruction
+    pub registers_before: Registers,
+}
+
+/*
+    A trace file in any of the formats, binary ones are recognized by their
+    header
+*/
+pub fn load(bytes: &[u8]) -> Result<Vec<TraceEntry>, Chip8Error> {
+    if bytes.starts_with(&MAGIC) {
+        return Ok(read_binary(bytes)?.into_iter().map(TraceEntry::from_record).collect());
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => parse_text(text),
+        Err(_) => Err(bad_trace("neither a binary trace nor text")),
+    }
+}
+
+pub fn parse_text(text: &str) -> Result<Vec<TraceEntry>, Chip8Error> {
+    let mut entries: Vec<TraceEntry> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line: &str = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry: TraceEntry = parse_line(line).map_err(|reason| bad_trace(&format!("line {}: {}", number + 1, reason)))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn parse_line(line: &str) -> Result<TraceEntry, String> {
+    let (head, changes): (&str, &str) = match line.find(';') {
+        Some(position) => (&line[..position], &line[position + 1..]),
+        None => (line, ""),
+    };
+
+    let mut fields = head.split_whitespace();
+    let frame: &str = fields.next().ok_or("missing frame")?;
+    let frame_known: bool = frame != "-";
+    let frame: u64 = if frame_known {
+        frame.parse().map_err(|_| format!("bad frame '{}'", frame))?
+    } else {
+        0
+    };
+    let pc: u16 = hex(fields.next().ok_or("missing pc")?, 0xFFFF)? as u16;
+    let opcode: u16 = hex(fields.next().ok_or("missing opcode")?, 0xFFFF)? as u16;
+
+    let mut record = TraceRecord { frame, pc, opcode, operand: 0, changes: Vec::new(), writes: Vec::new() };
+    for change in changes.split_whitespace() {
+        let (name, value): (&str, &str) = change.split_once('=').ok_or_else(|| format!("expected NAME=value, found '{}'", change))?;
+        if name.starts_with('[') && name.ends_with(']') {
+            let address: u16 = hex(&name[1..name.len() - 1], 0xFFFF)? as u16;
+            record.writes.push((address, hex(value, 0xFF)? as u8));
+            continue;
+        }
+        match Register::from_name(name) {
+            Some(Register::Pc) => continue,
+            Some(register) => {
+                let limit: u32 = if register == Register::I { 0xFFFF } else { 0xFF };
+                record.changes.push((register, hex(value, limit)? as u16));
+            }
+            None => return Err(format!("unknown register '{}'", name)),
+        }
+    }
+    Ok(TraceEntry { record, frame_known, text: String::from(line) })
+}
+
+fn hex(text: &str, limit: u32) -> Result<u32, String> {
+    let digits: &str = text.trim_start_matches("0x").trim_start_matches("0X").trim_start_matches(['#', '$']);
+    match u32::from_str_radix(digits, 16) {
+        Ok(value) if value <= limit => Ok(value),
+        Ok(_) => Err(format!("'{}' is out of range", text)),
+        Err(_) => Err(format!("bad hex value '{}'", text)),
+    }
+}
+
+fn bad_trace(reason: &str) -> Chip8Error {
+    Chip8Error::BadTrace { reason: String::from(reason) }
+}
+
+/*
+    The first instruction where the traces disagree, None when they match
+    all the way
+*/
+pub fn diff(ours: &[TraceEntry], reference: &[TraceEntry], options: &DiffOptions) -> Option<Divergence> {
+    let mut our_registers: Registers = [0;20];
+    let mut reference_registers: Registers = [0;20];
+
+    for index in 0..ours.len().max(reference.len()) {
+        let registers_before: Registers = our_registers;
+        let (our_entry, reference_entry) = match (ours.get(index), reference.get(index)) {
+            (Some(our_entry), Some(reference_entry)) => (our_entry, reference_entry),
+            _ => {
+                let differences: Vec<Difference> = vec![Difference::Length(ours.len(), reference.len())];
+                return Some(Divergence { index, differences, registers_before });
+            }
+        };
+        apply(&mut our_registers, &our_entry.record);
+        apply(&mut reference_registers, &reference_entry.record);
+
+        let differences: Vec<Difference> = compare(our_entry, reference_entry, &our_registers, &reference_registers, options);
+        if !differences.is_empty() {
+            return Some(Divergence { index, differences, registers_before });
+        }
+    }
+    None
+}
+
+fn apply(registers: &mut Registers, record: &TraceRecord) {
+    for (register, value) in &record.changes {
+        registers[register_id(*register) as usize] = *value;
+    }
+}
+
+fn compare(ours: &TraceEntry, reference: &TraceEntry, our_registers: &Registers, reference_registers: &Registers, options: &DiffOptions) -> Vec<Difference> {
+    let (ours_record, reference_record) = (&ours.record, &reference.record);
+    let mut differences: Vec<Difference> = Vec::new();
+    if options.compare_frames && ours.frame_known && reference.frame_known && ours_record.frame != reference_record.frame {
+        differences.push(Difference::Frame(ours_record.frame, reference_record.frame));
+    }
+    if ours_record.pc != reference_record.pc {
+        differences.push(Difference::Pc(ours_record.pc, reference_record.pc));
+    }
+    if ours_record.opcode != reference_record.opcode {
+        differences.push(Difference::Opcode(ours_record.opcode, reference_record.opcode));
+    }
+    for (index, register) in TRACED_REGISTERS.iter().enumerate() {
+        if our_registers[index] != reference_registers[index] {
+            differences.push(Difference::Register(*register, our_registers[index], reference_registers[index]));
+        }
+    }
+    if options.compare_memory && ours_record.writes != reference_record.writes {
+        differences.push(Difference::Writes(ours_record.writes.clone(), reference_record.writes.clone()));
+    }
+    differences
+}
+
+/*
+    The divergence spelled out: where it is, what differs, the registers going
+    in and both traces around it
+*/
+pub fn report(ours: &[TraceEntry], reference: &[TraceEntry], divergence: &Divergence, context: usize) -> String {
+    let index: usize = divergence.index;
+    let mut text: String = match ours.get(index).or_else(|| reference.get(index)) {
+        Some(entry) => format!(
+            "traces diverge at instruction {} (frame {}, pc {:04X}, {:04X} {})\n",
+            index, frame_text(entry), entry.record.pc, entry.record.opcode, describe(entry.record.opcode)
+        ),
+        None => format!("traces diverge at instruction {}\n", index),
+    };
+    for difference in &divergence.differences {
+        text.push_str(&format!("  {}\n", difference));
+    }
+
+    text.push_str("registers before:\n ");
+    for (register, value) in TRACED_REGISTERS.iter().zip(divergence.registers_before.iter()) {
+        text.push_str(&format!(" {}={}", register_name(*register), register_value(*register, *value)));
+    }
+    text.push('\n');
+
+    text.push_str("context:\n");
+    let last: usize = (index + context).min(ours.len().max(reference.len()).saturating_sub(1));
+    for line in index.saturating_sub(context)..=last {
+        let marker: char = if line == index { '>' } else { ' ' };
+        text.push_str(&format!("{} {:>8}  ours       {}\n", marker, line, entry_text(ours.get(line))));
+        text.push_str(&format!("  {:>8}  reference  {}\n", "", entry_text(reference.get(line))));
+    }
+    text
+}
+
+fn entry_text(entry: Option<&TraceEntry>) -> &str {
+    entry.map_or("(end of trace)", |entry| entry.text.as_str())
+}
+
+fn frame_text(entry: &TraceEntry) -> String {
+    if entry.frame_known {
+        entry.record.frame.to_string()
+    } else {
+        String::from("unknown")
+    }
+}
+
+fn describe(opcode: u16) -> String {
+    match decode(opcode) {
+        Some(instruction) => mnemonic(instruction, 0),
+        None => String::from("unknown opcode"),
+    }
+}
+
+fn register_value(register: Register, value: u16) -> String {
+    match register {
+        Register::I => format!("{:04X}", value),
+        _ => format!("{:02X}", value),
+    }
+}
+
+fn writes_text(writes: &[(u16, u8)]) -> String {
+    if writes.is_empty() {
+        return String::from("none");
+    }
+    writes.iter().map(|(address, value)| format!("[{:04X}]={:02X}", address, value)).collect::<Vec<String>>().join(" ")
+}
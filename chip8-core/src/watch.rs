@@ -0,0 +1,218 @@
+use std::fmt;
+
+/*
+    Memory watchpoints. The cpu reports every memory access made by the program
+    (opcode fetches, DXYN sprite reads, FX33/FX55/FX65 and the XO-CHIP loads and
+    stores) here, and accesses that fall in a watched range are kept as hits
+    for the frontend to log. A watchpoint can also ask to pause: the access is
+    noted and whoever drives the cpu stops once the instruction has finished.
+
+    Reads by debuggers and frontends through Chip8::get_memory and
+    Chip8::set_memory are not reported.
+*/
+
+// hits nobody collects are dropped past this many, so an unattended run can't grow without bound
+const MAX_PENDING_HITS: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    // the opcode (or the operand of F000 NNNN) being read for execution
+    Fetch,
+    Read,
+    Write,
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Access::Fetch => write!(f, "fetched"),
+            Access::Read => write!(f, "read"),
+            Access::Write => write!(f, "wrote"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchAction {
+    Break,
+    Log,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    // first and last watched address, inclusive
+    pub start: usize,
+    pub end: usize,
+    pub fetch: bool,
+    pub read: bool,
+    pub write: bool,
+    pub action: WatchAction,
+}
+
+impl Watchpoint {
+
+    /*
+        Parse "ADDR[-END] [ACCESS] [log]" where addresses are hex and ACCESS is
+        any of r, w and x (fetch), e.g. "3a0 w" or "300-30f rw log". Watches
+        reads and writes and breaks unless told otherwise. ':' works as a
+        separator too for command lines, as in "3a0:w:log".
+    */
+    pub fn parse(text: &str) -> Result<Watchpoint, String> {
+        let words: Vec<&str> = text.split(|c: char| c.is_whitespace() || c == ':').filter(|word| !word.is_empty()).collect();
+        let (range, options): (&str, &[&str]) = match words.split_first() {
+            Some((range, options)) => (range, options),
+            None => return Err(String::from("usage: ADDR[-END] [r|w|x] [log]")),
+        };
+
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (parse_address(start)?, parse_address(end)?),
+            None => (parse_address(range)?, parse_address(range)?),
+        };
+        if end < start {
+            return Err(format!("range {} ends before it starts", range));
+        }
+
+        let mut watchpoint = Watchpoint { start, end, fetch: false, read: true, write: true, action: WatchAction::Break };
+        for option in options {
+            match *option {
+                "log" => watchpoint.action = WatchAction::Log,
+                "break" => watchpoint.action = WatchAction::Break,
+                kinds if kinds.chars().all(|kind| "rwx".contains(kind)) => {
+                    watchpoint.read = kinds.contains('r');
+                    watchpoint.write = kinds.contains('w');
+                    watchpoint.fetch = kinds.contains('x');
+                }
+                _ => return Err(format!("unknown watch option '{}', expected r, w, x or log", option)),
+            }
+        }
+        Ok(watchpoint)
+    }
+
+    pub fn matches(&self, address: usize, access: Access) -> bool {
+        let wanted: bool = match access {
+            Access::Fetch => self.fetch,
+            Access::Read => self.read,
+            Access::Write => self.write,
+        };
+        wanted && (self.start..=self.end).contains(&address)
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{:#05x}", self.start)?;
+        } else {
+            write!(f, "{:#05x}-{:#05x}", self.start, self.end)?;
+        }
+        let kinds: String = [(self.read, 'r'), (self.write, 'w'), (self.fetch, 'x')].iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, kind)| *kind)
+            .collect();
+        let action: &str = match self.action {
+            WatchAction::Break => "break",
+            WatchAction::Log => "log",
+        };
+        write!(f, " {} {}", kinds, action)
+    }
+}
+
+fn parse_address(text: &str) -> Result<usize, String> {
+    usize::from_str_radix(text.trim_start_matches("0x"), 16).map_err(|_| format!("bad address '{}'", text))
+}
+
+/*
+    A watched access, with the pc of the instruction that made it
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    pub watchpoint: usize,
+    pub action: WatchAction,
+    pub pc: u16,
+    pub address: usize,
+    pub access: Access,
+    pub value: u8,
+    pub frame: u64,
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "watch {}: pc {:#05x} {} {:#04x} at {:#05x} (frame {})",
+            self.watchpoint, self.pc, self.access, self.value, self.address, self.frame)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Watchpoints {
+    points: Vec<Option<Watchpoint>>,
+    active: usize,
+    hits: Vec<WatchHit>,
+    break_hit: Option<usize>,
+}
+
+impl Watchpoints {
+    pub fn new() -> Watchpoints {
+        Watchpoints::default()
+    }
+
+    pub fn add(&mut self, watchpoint: Watchpoint) -> usize {
+        self.points.push(Some(watchpoint));
+        self.active += 1;
+        self.points.len() - 1
+    }
+
+    // numbers of the remaining watchpoints don't change
+    pub fn remove(&mut self, index: usize) -> bool {
+        match self.points.get_mut(index) {
+            Some(slot) if slot.is_some() => {
+                *slot = None;
+                self.active -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Watchpoint)> {
+        self.points.iter().enumerate().filter_map(|(index, slot)| slot.as_ref().map(|watchpoint| (index, watchpoint)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active == 0
+    }
+
+    /*
+        Called by the cpu for every access the program makes
+    */
+    pub(crate) fn check(&mut self, pc: u16, address: usize, access: Access, value: u8, frame: u64) {
+        if self.active == 0 {
+            return;
+        }
+        for (index, slot) in self.points.iter().enumerate() {
+            let watchpoint: &Watchpoint = match slot {
+                Some(watchpoint) if watchpoint.matches(address, access) => watchpoint,
+                _ => continue,
+            };
+            if watchpoint.action == WatchAction::Break && self.break_hit.is_none() {
+                self.break_hit = Some(index);
+            }
+            if self.hits.len() < MAX_PENDING_HITS {
+                self.hits.push(WatchHit { watchpoint: index, action: watchpoint.action, pc, address, access, value, frame });
+            }
+        }
+    }
+
+    /*
+        Hits since the last call, oldest first
+    */
+    pub fn take_hits(&mut self) -> Vec<WatchHit> {
+        std::mem::take(&mut self.hits)
+    }
+
+    /*
+        The watchpoint that asked to pause since the last call, if any
+    */
+    pub fn take_break(&mut self) -> Option<usize> {
+        self.break_hit.take()
+    }
+}
@@ -0,0 +1,111 @@
+use chip8_core::assembler::{assemble, AssemblyError};
+use chip8_core::disassembler::{disassemble, Syntax};
+
+/*
+    The extras on top of the mnemonics (expressions, labels, macros) assemble
+    to what they should or fail on the right line. Reassembling disassembled
+    ROMs is tested in disassembler.rs.
+*/
+
+const BRIX: &str = "../src/programs/BRIX";
+
+#[test]
+fn octo_listings_are_refused_with_a_hint() {
+    let rom: Vec<u8> = std::fs::read(BRIX).unwrap();
+    let listing: String = disassemble(&rom, Syntax::Octo);
+    let error: AssemblyError = assemble(&listing).unwrap_err();
+    assert!(error.message.contains("Octo"), "{}", error);
+}
+
+fn words(source: &str) -> Vec<u16> {
+    let rom: Vec<u8> = assemble(source).unwrap_or_else(|e| panic!("{}", e));
+    rom.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect()
+}
+
+fn error(source: &str) -> AssemblyError {
+    assemble(source).unwrap_err()
+}
+
+#[test]
+fn expressions_follow_precedence() {
+    assert_eq!(words("LD V0, 2 + 3 * 4"), [0x600E]);
+    assert_eq!(words("LD V0, (2 + 3) * 4"), [0x6014]);
+    assert_eq!(words("LD V0, 1 << 4 | 1"), [0x6011]);
+    assert_eq!(words("LD V0, #F0 & %11001100 ^ 0b1"), [0x60C1]);
+    assert_eq!(words("LD V0, 17 / 5 + 17 % 5"), [0x6005]);
+    assert_eq!(words("ADD V0, -1"), [0x70FF]);
+    assert_eq!(words("LD V0, ~0 & $FF"), [0x60FF]);
+    assert_eq!(words("LD V0, 'A'"), [0x6041]);
+    // $ alone is the address of the line
+    assert_eq!(words("CLS\nJP $"), [0x00E0, 0x1202]);
+}
+
+#[test]
+fn expression_errors_name_the_line() {
+    let cases: [(&str, &str);5] = [
+        ("CLS\nLD V0, 1 / 0", "division by zero"),
+        ("CLS\nLD V0, 1 % 0", "division by zero"),
+        ("CLS\nLD V0, (-9223372036854775807 - 1) / -1", "overflows"),
+        ("CLS\nLD V0, (-9223372036854775807 - 1) % -1", "overflows"),
+        ("CLS\nLD V0, -(-9223372036854775807 - 1)", "overflows"),
+    ];
+    for (source, message) in cases {
+        let error: AssemblyError = error(source);
+        assert_eq!(error.line, 2, "{}", source);
+        assert!(error.message.contains(message), "{}: {}", source, error);
+    }
+}
+
+#[test]
+fn labels_resolve_forward_and_backward() {
+    let source: &str = "
+        start:  CALL draw
+                JP start
+        draw:   LD I, ball
+                DRW V0, V1, ball_end - ball
+                RET
+        ball:   SPRITE .####.##
+                SPRITE ########
+        ball_end:
+        SIZE = ball_end - ball
+                DB SIZE";
+    let rom: Vec<u8> = assemble(source).unwrap();
+    assert_eq!(rom, [0x22, 0x04, 0x12, 0x00, 0xA2, 0x0A, 0xD0, 0x12, 0x00, 0xEE, 0x7B, 0xFF, 0x02]);
+}
+
+#[test]
+fn macros_expand_with_arguments_and_unique_labels() {
+    let source: &str = "
+        MACRO wait reg, ticks
+            LD reg, ticks
+            LD DT, reg
+        loop\\@: LD reg, DT
+            SE reg, 0
+            JP loop\\@
+        ENDM
+                wait V1, 10
+                wait V2, 20";
+    assert_eq!(words(source), [
+        0x610A, 0xF115, 0xF107, 0x3100, 0x1204,
+        0x6214, 0xF215, 0xF207, 0x3200, 0x120E,
+    ]);
+    assert!(error("MACRO m a\nCLS\nENDM\nm 1, 2").message.contains("takes 1 arguments, got 2"));
+}
+
+#[test]
+fn errors_name_the_line() {
+    let cases: [(&str, usize, &str);7] = [
+        ("CLS\n\nFOO V0", 3, "unknown instruction 'FOO'"),
+        ("CLS\nPLANE V0", 2, "bad operands for PLANE"),
+        ("CLS\nAUDIO 1", 2, "bad operands for AUDIO"),
+        ("CLS\nPITCH 3", 2, "bad operands for PITCH"),
+        ("JP nowhere", 1, "unknown name 'nowhere'"),
+        ("a: CLS\na: CLS", 2, "defined twice"),
+        ("CLS\nLD V0, 256", 2, "doesn't fit in a byte"),
+    ];
+    for (source, line, message) in cases {
+        let error: AssemblyError = error(source);
+        assert_eq!(error.line, line, "{}: {}", source, error);
+        assert!(error.message.contains(message), "{}: {}", source, error);
+    }
+}
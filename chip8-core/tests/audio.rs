@@ -0,0 +1,108 @@
+use chip8_core::audio::{AudioConfig, Oscillator, Pattern};
+use chip8_core::chip8::{Chip8, Mode};
+use chip8_core::headless::{HeadlessRunner, StopCondition, StopReason};
+use chip8_core::quirks::Quirks;
+
+/*
+    The headless runner's NullAudio records when the beeper turns on and off,
+    which has to follow the sound timer counting down once per frame, and the
+    XO-CHIP pattern the oscillator plays instead of the tone
+*/
+
+// beep for ST frames, wait 20 frames on the delay timer, beep again
+const PROGRAM: [u16;11] = [
+    0x600A, // 200  V0 = 10
+    0xF018, // 202  ST = V0
+    0x6114, // 204  V1 = 20
+    0xF115, // 206  DT = V1
+    0xF107, // 208  V1 = DT
+    0x3100, // 20A  skip if V1 == 0
+    0x1208, // 20C  jump 208
+    0x6005, // 20E  V0 = 5
+    0xF018, // 210  ST = V0
+    0x1212, // 212  jump 212
+    0x0000,
+];
+
+fn run(frames: u64) -> HeadlessRunner {
+    let mut cpu = Chip8::new(Quirks::default());
+    cpu.initialize();
+    let rom: Vec<u8> = PROGRAM.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    cpu.load_rom(&rom).unwrap();
+    let mut runner = HeadlessRunner::new(cpu);
+    runner.add_stop_condition(StopCondition::Frames(frames));
+    assert!(matches!(runner.run(), StopReason::FrameLimit));
+    runner
+}
+
+#[test]
+fn the_timeline_follows_the_sound_timer() {
+    let runner: HeadlessRunner = run(60);
+    let timeline: &[(u64, bool)] = runner.audio().timeline();
+    assert_eq!(timeline.len(), 4, "{:?}", timeline);
+
+    // on and off alternate. The state is taken after each frame's timer tick,
+    // so a beep set to N during a frame is heard for the N - 1 frames after it
+    assert!(timeline.iter().enumerate().all(|(index, (_, playing))| *playing == (index % 2 == 0)), "{:?}", timeline);
+    assert_eq!(timeline[1].0 - timeline[0].0, 10 - 1, "{:?}", timeline);
+    assert_eq!(timeline[3].0 - timeline[2].0, 5 - 1, "{:?}", timeline);
+    // the second beep starts when the 20 frame delay runs out
+    assert_eq!(timeline[2].0 - timeline[0].0, 20, "{:?}", timeline);
+    assert_eq!(runner.cpu().get_sound_timer(), 0);
+}
+
+#[test]
+fn a_beep_still_sounding_at_the_end_has_no_off_entry() {
+    let runner: HeadlessRunner = run(5);
+    assert_eq!(runner.audio().timeline().len(), 1);
+    assert!(runner.audio().timeline()[0].1);
+    assert_eq!(runner.cpu().get_sound_timer(), 10 - 5);
+}
+
+#[test]
+fn xo_chip_patterns_reach_the_sink_with_their_rate() {
+    // pattern at 300, F002 loads it, pitch 112 doubles the rate, beep
+    let mut cpu = Chip8::new(Quirks::XO_CHIP);
+    cpu.set_mode(Mode::XoChip);
+    cpu.initialize();
+    let program: [u16;8] = [0xA300, 0xF002, 0x6070, 0xF03A, 0x6002, 0xF018, 0x120C, 0x0000];
+    let rom: Vec<u8> = program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    cpu.load_rom(&rom).unwrap();
+    for offset in 0..16 {
+        cpu.set_memory(0x300 + offset, 0xF0).unwrap();
+    }
+    let mut runner = HeadlessRunner::new(cpu);
+    runner.add_stop_condition(StopCondition::Frames(10));
+    runner.run();
+
+    let patterns: &[(u64, Option<Pattern>)] = runner.audio().patterns();
+    assert_eq!(patterns.len(), 1, "{:?}", patterns);
+    let pattern: Pattern = patterns[0].1.unwrap();
+    assert_eq!(pattern.bits, [0xF0;16]);
+    assert_eq!(pattern.rate, 8000.0);
+    assert_eq!(runner.audio().timeline()[0], (1, true));
+}
+
+#[test]
+fn plain_chip8_has_no_pattern() {
+    let runner: HeadlessRunner = run(20);
+    assert!(runner.audio().patterns().is_empty());
+}
+
+#[test]
+fn the_oscillator_plays_the_pattern_bits() {
+    // one pattern sample per output sample
+    let mut oscillator = Oscillator::new(AudioConfig { volume: 1.0, ..AudioConfig::default() }, 8000.0);
+    let mut bits = [0u8;16];
+    bits[0] = 0b1100_0000;
+    oscillator.pattern = Some(Pattern { bits, rate: 8000.0 });
+    oscillator.playing = true;
+    let samples: Vec<f32> = (0..130).map(|_| oscillator.next_sample()).collect();
+    assert_eq!(samples[..4], [1.0, 1.0, -1.0, -1.0]);
+    assert!(samples[2..128].iter().all(|&sample| sample == -1.0));
+    // it loops
+    assert_eq!(samples[128..], [1.0, 1.0]);
+
+    oscillator.playing = false;
+    assert_eq!(oscillator.next_sample(), 0.0);
+}
@@ -0,0 +1,154 @@
+use chip8_core::chip8::Chip8;
+use chip8_core::debugger::{BreakReason, DebugCommand, DebugEvent, Debugger, Register};
+use chip8_core::quirks::Quirks;
+
+/*
+    Drives the debugger the way the frontend does, one run_frame per frame
+    with commands typed in between, over a loop that calls a subroutine
+*/
+
+const PROGRAM: [u16;7] = [
+    0x6000, // 200  V0 = 0
+    0x2208, // 202  call 208
+    0x7001, // 204  V0 += 1
+    0x1202, // 206  jump 202
+    0x7101, // 208  V1 += 1
+    0x7101, // 20A  V1 += 1
+    0x00EE, // 20C  return
+];
+
+fn start() -> (Debugger, Chip8) {
+    let mut cpu = Chip8::new(Quirks::default());
+    cpu.initialize();
+    let rom: Vec<u8> = PROGRAM.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    cpu.load_rom(&rom).unwrap();
+    (Debugger::new(), cpu)
+}
+
+fn command(debugger: &mut Debugger, cpu: &mut Chip8, line: &str) -> String {
+    debugger.execute(DebugCommand::parse(line).unwrap(), cpu)
+}
+
+// frames until the debugger stops, panics if it never does
+fn run_until_break(debugger: &mut Debugger, cpu: &mut Chip8) -> BreakReason {
+    for _ in 0..10 {
+        if let DebugEvent::Break(reason) = debugger.run_frame(cpu).unwrap() {
+            return reason;
+        }
+    }
+    panic!("the debugger never stopped, pc {:#05x}", cpu.get_pc());
+}
+
+#[test]
+fn breakpoints_stop_before_the_instruction() {
+    let (mut debugger, mut cpu) = start();
+    assert_eq!(command(&mut debugger, &mut cpu, "b 204"), "breakpoint 0 at 0x204");
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Breakpoint(0));
+    assert_eq!((cpu.get_pc(), cpu.get_v()[0]), (0x204, 0));
+    assert!(debugger.is_paused());
+    assert_eq!(debugger.run_frame(&mut cpu).unwrap(), DebugEvent::Idle);
+
+    // continuing runs the instruction and stops the next time round
+    command(&mut debugger, &mut cpu, "c");
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Breakpoint(0));
+    assert_eq!((cpu.get_pc(), cpu.get_v()[0]), (0x204, 1));
+
+    // deleted, the loop runs whole frames
+    assert_eq!(command(&mut debugger, &mut cpu, "d 0"), "deleted breakpoint 0");
+    command(&mut debugger, &mut cpu, "c");
+    assert_eq!(debugger.run_frame(&mut cpu).unwrap(), DebugEvent::Frame);
+    assert_eq!(debugger.run_frame(&mut cpu).unwrap(), DebugEvent::Frame);
+}
+
+#[test]
+fn breakpoints_past_the_address_space_are_refused() {
+    assert_eq!(DebugCommand::parse("b 12345").unwrap_err(), "bad address '12345'");
+    assert_eq!(DebugCommand::parse("b 12345 if v0 == 1").unwrap_err(), "bad address '12345'");
+    assert!(DebugCommand::parse("b ffff").is_ok());
+}
+
+#[test]
+fn conditional_breakpoints() {
+    let (mut debugger, mut cpu) = start();
+    command(&mut debugger, &mut cpu, "b 204 if v0 == 3");
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Breakpoint(0));
+    assert_eq!((cpu.get_pc(), cpu.get_v()[0]), (0x204, 3));
+}
+
+#[test]
+fn condition_only_breakpoints_fire_on_the_edge() {
+    let (mut debugger, mut cpu) = start();
+    command(&mut debugger, &mut cpu, "b if v1 >= 4");
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Breakpoint(0));
+    assert_eq!((cpu.get_pc(), cpu.get_v()[1]), (0x20C, 4));
+
+    // still true, but it doesn't fire again while it stays true
+    command(&mut debugger, &mut cpu, "c");
+    for _ in 0..5 {
+        assert_eq!(debugger.run_frame(&mut cpu).unwrap(), DebugEvent::Frame);
+    }
+
+    // once false it fires the next time it becomes true
+    cpu.set_v(1, 0).unwrap();
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Breakpoint(0));
+    assert_eq!(cpu.get_v()[1], 4);
+}
+
+#[test]
+fn step_into_and_out_of_a_call() {
+    let (mut debugger, mut cpu) = start();
+    debugger.pause();
+    assert_eq!(debugger.run_frame(&mut cpu).unwrap(), DebugEvent::Idle);
+
+    command(&mut debugger, &mut cpu, "s");
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Step);
+    assert_eq!(cpu.get_pc(), 0x202);
+    command(&mut debugger, &mut cpu, "s");
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Step);
+    assert_eq!((cpu.get_pc(), cpu.get_sp()), (0x208, 1));
+    command(&mut debugger, &mut cpu, "s");
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Step);
+    assert_eq!(cpu.get_pc(), 0x20A);
+
+    // out runs the rest of the subroutine and stops after the return
+    assert_eq!(command(&mut debugger, &mut cpu, "o"), "");
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Step);
+    assert_eq!((cpu.get_pc(), cpu.get_sp(), cpu.get_v()[1]), (0x204, 0, 2));
+    assert!(debugger.is_paused());
+
+    // there is nothing to step out of at the top level
+    assert_eq!(command(&mut debugger, &mut cpu, "o"), "not inside a subroutine");
+    assert_eq!(debugger.run_frame(&mut cpu).unwrap(), DebugEvent::Idle);
+}
+
+#[test]
+fn step_over_runs_the_call_and_stops_after_it() {
+    let (mut debugger, mut cpu) = start();
+    debugger.pause();
+    command(&mut debugger, &mut cpu, "s");
+    run_until_break(&mut debugger, &mut cpu);
+    assert_eq!(cpu.get_pc(), 0x202);
+
+    command(&mut debugger, &mut cpu, "n");
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Step);
+    assert_eq!((cpu.get_pc(), cpu.get_sp(), cpu.get_v()[1]), (0x204, 0, 2));
+
+    // on anything but a call it is a plain step
+    command(&mut debugger, &mut cpu, "n");
+    assert_eq!(run_until_break(&mut debugger, &mut cpu), BreakReason::Step);
+    assert_eq!((cpu.get_pc(), cpu.get_v()[0]), (0x206, 1));
+}
+
+#[test]
+fn registers_that_do_not_exist_are_refused() {
+    let (_, mut cpu) = start();
+    assert!(cpu.set_v(16, 1).is_err());
+    assert!(cpu.set_sp(17).is_err());
+    assert!(Register::V(0x1F).write(&mut cpu, 1).is_err());
+    assert!(Register::Sp.write(&mut cpu, 0x100).is_err());
+    assert_eq!((cpu.get_v().iter().sum::<u8>(), cpu.get_sp()), (0, 0));
+
+    Register::Sp.write(&mut cpu, 16).unwrap();
+    Register::V(0xF).write(&mut cpu, 0x1FF).unwrap();
+    assert_eq!((cpu.get_sp(), cpu.get_v()[0xF]), (16, 0xFF));
+}
@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use chip8_core::assembler::assemble;
+use chip8_core::disassembler::{disassemble, Syntax};
+
+/*
+    Listings of the bundled ROMs: every byte shows up once, as code or data,
+    labels sit on the lines they name, and the Cowgod listing assembles back
+    to the ROM
+*/
+
+const PROGRAMS: &str = "../src/programs";
+
+fn programs() -> Vec<(String, Vec<u8>)> {
+    let mut programs: Vec<(String, Vec<u8>)> = std::fs::read_dir(PROGRAMS).unwrap()
+        .map(|entry| entry.unwrap().path())
+        .map(|path: PathBuf| (path.file_name().unwrap().to_string_lossy().into_owned(), std::fs::read(&path).unwrap()))
+        .collect();
+    programs.sort();
+    programs
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Kind {
+    Code,
+    Data,
+}
+
+struct Line {
+    address: usize,
+    kind: Kind,
+    text: String,
+    bytes: Vec<u8>,
+    // what the comment shows after the address: the opcode or sprite art
+    note: String,
+}
+
+// the listing's lines in order, and the address of each label
+fn parse(listing: &str) -> (Vec<Line>, BTreeMap<String, usize>) {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut pending: Vec<String> = Vec::new();
+    let mut labels: BTreeMap<String, usize> = BTreeMap::new();
+    for raw in listing.lines().skip(1) {
+        let raw: &str = raw.trim();
+        if raw.is_empty() {
+            continue;
+        }
+        if let Some(label) = raw.strip_suffix(':') {
+            pending.push(String::from(label));
+            continue;
+        }
+        let (text, comment) = raw.split_once(';').unwrap();
+        let (address, note) = comment.trim().split_once("  ").unwrap_or((comment.trim(), ""));
+        let address: usize = usize::from_str_radix(address, 16).unwrap();
+        let text: String = String::from(text.trim());
+        let (kind, bytes): (Kind, Vec<u8>) = match text.strip_prefix("DB ") {
+            Some(values) => (Kind::Data, values.split(", ").map(|value| u8::from_str_radix(&value[1..], 16).unwrap()).collect()),
+            None => (Kind::Code, (0..note.len()).step_by(2).map(|start| u8::from_str_radix(&note[start..start + 2], 16).unwrap()).collect()),
+        };
+        for label in pending.drain(..) {
+            labels.insert(label, address);
+        }
+        lines.push(Line { address, kind, text, bytes, note: String::from(note) });
+    }
+    (lines, labels)
+}
+
+#[test]
+fn every_byte_is_listed_once_as_code_or_data() {
+    for (name, rom) in programs() {
+        let (lines, _) = parse(&disassemble(&rom, Syntax::Cowgod));
+        let mut address: usize = 0x200;
+        for line in &lines {
+            assert_eq!(line.address, address, "{}: line '{}' is out of place", name, line.text);
+            let index: usize = address - 0x200;
+            assert_eq!(line.bytes, rom[index..index + line.bytes.len()], "{}: {:03X} {}", name, address, line.text);
+            address += line.bytes.len();
+        }
+        assert_eq!(address, 0x200 + rom.len(), "{}", name);
+        assert_eq!(lines[0].kind, Kind::Code, "{} doesn't start with code", name);
+    }
+}
+
+#[test]
+fn labels_name_code_and_the_data_i_points_at() {
+    for (name, rom) in programs() {
+        let (lines, labels) = parse(&disassemble(&rom, Syntax::Cowgod));
+        let kinds: BTreeMap<usize, &Kind> = lines.iter().map(|line| (line.address, &line.kind)).collect();
+        for (label, address) in &labels {
+            assert_eq!(label[1..], format!("{:03X}", address), "{}: {} is on the wrong line", name, label);
+            if label.starts_with('L') {
+                assert_eq!(kinds[address], &Kind::Code, "{}: jump target {} is data", name, label);
+            }
+        }
+        // operands naming a label refer to one that exists
+        for line in lines.iter().filter(|line| line.kind == Kind::Code) {
+            let operand: &str = line.text.rsplit([' ', ',']).next().unwrap();
+            if operand.len() == 4 && (operand.starts_with('L') || operand.starts_with('D')) {
+                assert!(labels.contains_key(operand), "{}: {} uses an undefined label", name, line.text);
+            }
+        }
+    }
+}
+
+#[test]
+fn sprite_data_is_listed_as_art() {
+    let rom: Vec<u8> = std::fs::read(format!("{}/BRIX", PROGRAMS)).unwrap();
+    let (lines, labels) = parse(&disassemble(&rom, Syntax::Cowgod));
+    // LD I, D30C then DRW draws the brick
+    assert!(lines.iter().any(|line| line.text == "LD I, D30C"));
+    let brick: &Line = lines.iter().find(|line| line.address == labels["D30C"]).unwrap();
+    assert_eq!((&brick.kind, brick.text.as_str(), brick.note.as_str()), (&Kind::Data, "DB #E0", "###....."));
+}
+
+#[test]
+fn cowgod_listings_assemble_to_the_same_bytes() {
+    let programs: Vec<(String, Vec<u8>)> = programs();
+    assert!(programs.len() > 20);
+    for (name, rom) in programs {
+        let listing: String = disassemble(&rom, Syntax::Cowgod);
+        let assembled: Vec<u8> = assemble(&listing).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        assert!(assembled == rom, "{} doesn't assemble back to the same bytes", name);
+    }
+}
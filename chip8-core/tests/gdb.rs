@@ -0,0 +1,165 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use chip8_core::chip8::Chip8;
+use chip8_core::debugger::{DebugEvent, Debugger};
+use chip8_core::gdb::{GdbEvent, GdbServer};
+use chip8_core::quirks::Quirks;
+
+/*
+    Drives the GDB server with a client on a loopback socket, the way the
+    frontend's loop does: poll between frames, run frames through the debugger
+*/
+
+// V0 = 1, then V0 += 1 forever
+const PROGRAM: [u16;3] = [0x6001, 0x7001, 0x1202];
+
+struct Session {
+    server: GdbServer,
+    client: TcpStream,
+    debugger: Debugger,
+    cpu: Chip8,
+}
+
+impl Session {
+    fn start() -> (Session, Vec<GdbEvent>) {
+        let mut cpu = Chip8::new(Quirks::default());
+        cpu.initialize();
+        let rom: Vec<u8> = PROGRAM.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+        cpu.load_rom(&rom).unwrap();
+
+        let server: GdbServer = GdbServer::listen("127.0.0.1:0").unwrap();
+        let client: TcpStream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        client.set_nodelay(true).unwrap();
+        let mut session = Session { server, client, debugger: Debugger::new(), cpu };
+        let events: Vec<GdbEvent> = session.poll();
+        (session, events)
+    }
+
+    fn poll(&mut self) -> Vec<GdbEvent> {
+        // the client's bytes may take a moment to arrive on the server side
+        std::thread::sleep(Duration::from_millis(20));
+        self.server.poll(&mut self.debugger, &mut self.cpu, false)
+    }
+
+    fn send(&mut self, packet: &str) {
+        let checksum: u8 = packet.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        self.client.write_all(format!("${}#{:02x}", packet, checksum).as_bytes()).unwrap();
+    }
+
+    // the next packet from the server, skipping acks and checking the checksum
+    fn reply(&mut self) -> String {
+        let mut received: Vec<u8> = Vec::new();
+        let mut byte = [0u8;1];
+        while received.len() < 3 || received[received.len() - 3] != b'#' {
+            self.client.read_exact(&mut byte).unwrap();
+            if !(received.is_empty() && byte[0] == b'+') {
+                received.push(byte[0]);
+            }
+        }
+        let text: String = String::from_utf8(received).unwrap();
+        let (data, checksum) = text[1..].split_once('#').unwrap();
+        let sum: u8 = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        assert_eq!(format!("{:02x}", sum), checksum, "bad checksum on {}", text);
+        String::from(data)
+    }
+
+    fn request(&mut self, packet: &str) -> String {
+        self.send(packet);
+        self.poll();
+        self.reply()
+    }
+
+    fn run_until_break(&mut self) {
+        for _ in 0..10 {
+            if let DebugEvent::Break(_) = self.debugger.run_frame(&mut self.cpu).unwrap() {
+                return;
+            }
+        }
+        panic!("the debugger never stopped, pc {:#05x}", self.cpu.get_pc());
+    }
+}
+
+#[test]
+fn attaching_pauses_and_reports_registers_and_memory() {
+    let (mut session, events) = Session::start();
+    assert!(matches!(events[..], [GdbEvent::Connected(_)]), "{:?}", events);
+    assert!(session.debugger.is_paused());
+
+    assert_eq!(session.request("?"), "S05");
+    assert!(session.request("qSupported:swbreak+").contains("qXfer:features:read+"));
+    assert!(session.request("qXfer:features:read:target.xml:0,1000").contains("<reg name=\"pc\" bitsize=\"16\" regnum=\"20\""));
+
+    // V0-VF, I, SP, DT, ST, then PC
+    let registers: String = session.request("g");
+    assert_eq!(registers.len(), (16 + 2 + 1 + 1 + 1 + 2) * 2);
+    assert!(registers.ends_with("0200"), "{}", registers);
+    assert_eq!(session.request("p14"), "0200");
+    assert_eq!(session.request("m200,6"), "600170011202");
+    assert_eq!(session.request("m1000,2"), "E01");
+}
+
+#[test]
+fn breakpoints_continue_and_step() {
+    let (mut session, _) = Session::start();
+    assert_eq!(session.request("Z0,204,2"), "OK");
+    session.send("c");
+    session.poll();
+    session.run_until_break();
+    session.poll();
+    assert_eq!(session.reply(), "S05");
+    assert_eq!(session.cpu.get_pc(), 0x204);
+
+    session.send("s");
+    session.poll();
+    session.run_until_break();
+    session.poll();
+    assert_eq!(session.reply(), "S05");
+    assert_eq!(session.cpu.get_pc(), 0x202);
+    assert_eq!(session.cpu.get_v()[0], 2);
+
+    // removed, the loop runs through the frame
+    assert_eq!(session.request("z0,204,2"), "OK");
+    session.send("c");
+    session.poll();
+    assert_eq!(session.debugger.run_frame(&mut session.cpu).unwrap(), DebugEvent::Frame);
+
+    // the break character stops it again
+    session.client.write_all(&[0x03]).unwrap();
+    session.poll();
+    assert_eq!(session.reply(), "S02");
+}
+
+#[test]
+fn registers_and_memory_can_be_written() {
+    let (mut session, _) = Session::start();
+    assert_eq!(session.request("P0=2a"), "OK");
+    assert_eq!(session.request("P10=0345"), "OK");
+    assert_eq!(session.request("P11=20"), "E02");
+    assert_eq!(session.request("M300,3:abcdef"), "OK");
+    assert_eq!(session.cpu.get_v()[0], 0x2a);
+    assert_eq!(session.cpu.get_i(), 0x345);
+    assert_eq!(&session.cpu.get_memory()[0x300..0x303], &[0xab, 0xcd, 0xef]);
+
+    assert_eq!(session.request("Z0,206,2"), "OK");
+    assert_eq!(session.request("D"), "OK");
+    assert_eq!(session.poll(), Vec::new());
+    assert!(!session.server.is_connected());
+    assert!(!session.debugger.is_paused());
+    assert_eq!(session.debugger.breakpoints().count(), 0);
+}
+
+#[test]
+fn huge_lengths_are_capped_or_refused() {
+    let (mut session, _) = Session::start();
+    // replies stay within the advertised packet size
+    let memory: String = session.request("m0,ffffffffffffffff");
+    assert_eq!(memory.len(), 0x1000, "{}", memory.len());
+    assert!(session.request("qXfer:features:read:target.xml:10,ffffffffffffffff").starts_with('l'));
+    assert_eq!(session.request("Mffffffffffffffff,1:00"), "E01");
+    assert_eq!(session.request("M200,ffffffffffffffff:00"), "E01");
+    // still answering
+    assert_eq!(session.request("m200,2"), "6001");
+}
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use std::process::{Command, Output};
+
+/*
+    Runs the chip8-headless binary the way CI scripts do and checks that what
+    it writes to stdout can be read back: nothing but the JSON or the screen.
+    The JSON is read with a small parser here, the crate has no dependencies
+    to pull one from.
+*/
+
+const PONG: &str = "../src/programs/PONG";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(text: &'a str) -> Result<Json, String> {
+        let mut parser = Parser { text: text.as_bytes(), position: 0 };
+        let value: Json = parser.value()?;
+        parser.whitespace();
+        match parser.position == parser.text.len() {
+            true => Ok(value),
+            false => Err(format!("trailing text at byte {}", parser.position)),
+        }
+    }
+
+    fn whitespace(&mut self) {
+        while self.text.get(self.position).is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.whitespace();
+        match self.text.get(self.position) {
+            Some(&found) if found == byte => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(format!("expected '{}' at byte {}", byte as char, self.position)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        let rest: &[u8] = &self.text[self.position..];
+        match rest.first() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(Json::Text),
+            Some(b't') if rest.starts_with(b"true") => self.word(4, Json::Bool(true)),
+            Some(b'f') if rest.starts_with(b"false") => self.word(5, Json::Bool(false)),
+            Some(b'n') if rest.starts_with(b"null") => self.word(4, Json::Null),
+            Some(byte) if *byte == b'-' || byte.is_ascii_digit() => self.number(),
+            _ => Err(format!("unexpected input at byte {}", self.position)),
+        }
+    }
+
+    fn word(&mut self, length: usize, value: Json) -> Result<Json, String> {
+        self.position += length;
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start: usize = self.position;
+        while self.text.get(self.position).is_some_and(|byte| b"-+.eE0123456789".contains(byte)) {
+            self.position += 1;
+        }
+        let digits: &str = std::str::from_utf8(&self.text[start..self.position]).unwrap();
+        digits.parse().map(Json::Number).map_err(|_| format!("bad number '{}'", digits))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut text: String = String::new();
+        loop {
+            let byte: u8 = *self.text.get(self.position).ok_or("unterminated string")?;
+            self.position += 1;
+            match byte {
+                b'"' => return Ok(text),
+                b'\\' => {
+                    let escaped: u8 = *self.text.get(self.position).ok_or("unterminated string")?;
+                    self.position += 1;
+                    text.push(match escaped {
+                        b'n' => '\n',
+                        b't' => '\t',
+                        b'"' | b'\\' | b'/' => escaped as char,
+                        _ => return Err(format!("unsupported escape '\\{}'", escaped as char)),
+                    });
+                }
+                byte if byte < 0x20 => return Err(String::from("control character in string")),
+                byte => text.push(byte as char),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect(b'[')?;
+        let mut items: Vec<Json> = Vec::new();
+        self.whitespace();
+        if self.text.get(self.position) == Some(&b']') {
+            self.position += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.whitespace();
+            match self.text.get(self.position) {
+                Some(b',') => self.position += 1,
+                _ => break,
+            }
+        }
+        self.expect(b']')?;
+        Ok(Json::Array(items))
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect(b'{')?;
+        let mut fields: BTreeMap<String, Json> = BTreeMap::new();
+        self.whitespace();
+        if self.text.get(self.position) == Some(&b'}') {
+            self.position += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.whitespace();
+            let key: String = self.string()?;
+            self.expect(b':')?;
+            fields.insert(key, self.value()?);
+            self.whitespace();
+            match self.text.get(self.position) {
+                Some(b',') => self.position += 1,
+                _ => break,
+            }
+        }
+        self.expect(b'}')?;
+        Ok(Json::Object(fields))
+    }
+}
+
+fn headless(args: &[&str]) -> Output {
+    let output: Output = Command::new(env!("CARGO_BIN_EXE_chip8-headless")).args(args).output().unwrap();
+    assert!(output.status.success(), "chip8-headless {:?} failed:\n{}", args, String::from_utf8_lossy(&output.stderr));
+    output
+}
+
+#[test]
+fn json_on_stdout_parses() {
+    let output: Output = headless(&[PONG, "--frames", "10", "--json", "-"]);
+    let stdout: String = String::from_utf8(output.stdout).unwrap();
+    let json: Json = Parser::parse(&stdout).unwrap_or_else(|e| panic!("{}:\n{}", e, stdout));
+
+    let fields: BTreeMap<String, Json> = match json {
+        Json::Object(fields) => fields,
+        other => panic!("expected an object, got {:?}", other),
+    };
+    assert_eq!(fields["stop_reason"], Json::Text(String::from("frame limit reached")));
+    assert_eq!(fields["frames"], Json::Number(10.0));
+    assert!(matches!(&fields["v"], Json::Array(v) if v.len() == 16), "{:?}", fields["v"]);
+    for key in ["pc", "opcode", "i", "sp", "stack", "delay_timer", "sound_timer", "sound_timeline"] {
+        assert!(fields.contains_key(key), "missing {}", key);
+    }
+}
+
+#[test]
+fn text_on_stdout_is_only_the_screen() {
+    let output: Output = headless(&[PONG, "--frames", "10", "--text", "-"]);
+    let stdout: String = String::from_utf8(output.stdout).unwrap();
+    let rows: Vec<&str> = stdout.lines().collect();
+    assert_eq!(rows.len(), 32, "{}", stdout);
+    assert!(rows.iter().all(|row| row.len() == 64 && row.chars().all(|c| c == '#' || c == '.')), "{}", stdout);
+}
+
+#[test]
+fn bad_limits_are_usage_errors() {
+    let cases: [&[&str];6] = [
+        &["--timeout", "-1"],
+        &["--timeout", "NaN"],
+        &["--timeout", "1e300"],
+        &["--timeout", "0"],
+        &["--speed", "0"],
+        &["--png-scale", "0"],
+    ];
+    for flags in cases {
+        let output: Output = Command::new(env!("CARGO_BIN_EXE_chip8-headless")).arg(PONG).args(flags).output().unwrap();
+        let stderr: String = String::from_utf8_lossy(&output.stderr).into_owned();
+        assert_eq!(output.status.code(), Some(2), "{:?}: {}", flags, stderr);
+        assert!(stderr.contains(flags[0]), "{:?}: {}", flags, stderr);
+    }
+}
@@ -0,0 +1,118 @@
+use chip8_core::chip8::{Chip8, Mode};
+use chip8_core::error::Chip8Error;
+use chip8_core::headless::{HeadlessRunner, StopCondition, StopReason};
+use chip8_core::movie::Movie;
+use chip8_core::quirks::Quirks;
+use chip8_core::random::RandomKind;
+
+/*
+    A movie survives being written out and read back, and replaying it the
+    way chip8-headless --play does ends on the same screen as the recorded run
+*/
+
+const BRIX: &str = "../src/programs/BRIX";
+const FRAMES: u64 = 300;
+
+fn rom() -> Vec<u8> {
+    std::fs::read(BRIX).unwrap()
+}
+
+// a recording setup that differs from the defaults everywhere
+fn recording_cpu() -> Chip8 {
+    let mut cpu = Chip8::new(Quirks::COSMAC_VIP);
+    cpu.set_mode(Mode::Chip8);
+    cpu.set_speed(900);
+    cpu.seed_rng(42);
+    cpu.set_random_kind(RandomKind::Lcg);
+    cpu.initialize();
+    cpu.load_rom(&rom()).unwrap();
+    cpu
+}
+
+// paddle left for a while, then right, then nothing
+fn keys_for(frame: u64) -> [u8;16] {
+    let mut keys = [0;16];
+    match frame {
+        20..=79 => keys[4] = 1,
+        120..=199 => keys[6] = 1,
+        _ => {}
+    }
+    keys
+}
+
+// plays the keys the way the frontend does, returning the movie and the final screen
+fn record() -> (Movie, Vec<u8>) {
+    let mut cpu = recording_cpu();
+    let mut movie = Movie::new(&cpu);
+    for frame in 0..FRAMES {
+        let keys: [u8;16] = keys_for(frame);
+        movie.record(frame, &keys);
+        cpu.update_keystate(&keys);
+        cpu.run_frame().unwrap();
+    }
+    (movie, cpu.get_gfx().to_vec())
+}
+
+fn replay(movie: &Movie) -> Vec<u8> {
+    let mut cpu = Chip8::new(Quirks::default());
+    movie.configure(&mut cpu);
+    cpu.initialize();
+    cpu.load_rom(&rom()).unwrap();
+    movie.check_rom(&cpu).unwrap();
+
+    let mut runner = HeadlessRunner::new(cpu);
+    runner.set_input(movie.input_script());
+    runner.add_stop_condition(StopCondition::Frames(movie.length()));
+    assert!(matches!(runner.run(), StopReason::FrameLimit));
+    runner.cpu().get_gfx().to_vec()
+}
+
+#[test]
+fn movies_round_trip_through_bytes() {
+    let (movie, _) = record();
+    let bytes: Vec<u8> = movie.to_bytes();
+    let read: Movie = Movie::from_bytes(&bytes).unwrap();
+    assert_eq!(read.to_bytes(), bytes);
+    assert_eq!(read.length(), FRAMES);
+    assert_eq!((read.mode, read.quirks, read.speed, read.random_kind, read.seed), (Mode::Chip8, Quirks::COSMAC_VIP, 900, Some(RandomKind::Lcg), 42));
+    for frame in [0, 20, 79, 80, 150, 299] {
+        assert_eq!(read.keys_at(frame), keys_for(frame), "frame {}", frame);
+    }
+}
+
+#[test]
+fn bad_movie_files_are_refused() {
+    let (movie, _) = record();
+    let bytes: Vec<u8> = movie.to_bytes();
+    let refused = |bytes: &[u8]| matches!(Movie::from_bytes(bytes), Err(Chip8Error::BadMovie { .. }));
+
+    let mut bad_magic: Vec<u8> = bytes.clone();
+    bad_magic[..4].copy_from_slice(b"C8SV");
+    assert!(refused(&bad_magic));
+    let mut bad_version: Vec<u8> = bytes.clone();
+    bad_version[5] ^= 0xFF;
+    assert!(refused(&bad_version));
+    assert!(refused(&bytes[..bytes.len() - 1]));
+    assert!(refused(&bytes[..10]));
+    assert!(refused(&[]));
+
+    // a recording of another rom doesn't play on this one
+    let mut other = Chip8::new(Quirks::default());
+    other.initialize();
+    other.load_rom(&std::fs::read("../src/programs/PONG").unwrap()).unwrap();
+    assert!(matches!(movie.check_rom(&other), Err(Chip8Error::BadMovie { .. })));
+}
+
+#[test]
+fn replaying_ends_on_the_recorded_screen() {
+    let (movie, recorded) = record();
+    let movie: Movie = Movie::from_bytes(&movie.to_bytes()).unwrap();
+    assert!(replay(&movie) == recorded, "the replay drifted from the recording");
+
+    // the input mattered, so the comparison means something
+    let mut idle = recording_cpu();
+    for _ in 0..FRAMES {
+        idle.run_frame().unwrap();
+    }
+    assert!(idle.get_gfx() != recorded.as_slice());
+}
@@ -0,0 +1,604 @@
+use chip8_core::chip8::{Chip8, Mode};
+use chip8_core::error::Chip8Error;
+use chip8_core::quirks::{IndexIncrement, Quirks};
+use chip8_core::random::RandomSource;
+
+/*
+    One test per opcode (or family), each running a few hand written
+    instructions and checking registers, memory and the screen afterwards
+*/
+
+const VF: usize = 15;
+
+fn cpu_with(quirks: Quirks, mode: Mode, program: &[u16]) -> Chip8 {
+    let mut cpu = Chip8::new(quirks);
+    cpu.set_mode(mode);
+    cpu.initialize();
+    let rom: Vec<u8> = program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    cpu.load_rom(&rom).unwrap();
+    cpu
+}
+
+fn cpu(program: &[u16]) -> Chip8 {
+    cpu_with(Quirks::MODERN, Mode::Chip8, program)
+}
+
+fn run(cpu: &mut Chip8, cycles: usize) {
+    for _ in 0..cycles {
+        cpu.emulate_cycle().unwrap();
+    }
+}
+
+fn pixel(cpu: &Chip8, x: usize, y: usize) -> u8 {
+    let (width, _) = cpu.get_resolution();
+    cpu.get_gfx()[x + y * width]
+}
+
+fn lit_pixels(cpu: &Chip8) -> usize {
+    let (width, height) = cpu.get_resolution();
+    cpu.get_gfx()[..width * height].iter().filter(|&&value| value != 0).count()
+}
+
+// always hands out the same byte so CXNN results are known
+struct FixedRandom(u8);
+
+impl RandomSource for FixedRandom {
+    fn next_byte(&mut self) -> u8 {
+        self.0
+    }
+
+    fn state(&self) -> u64 {
+        self.0 as u64
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.0 = state as u8;
+    }
+}
+
+#[test]
+fn clear_screen_00e0() {
+    // draw the 0 glyph, then clear
+    let mut cpu = cpu(&[0xF029, 0xD015, 0x00E0]);
+    run(&mut cpu, 2);
+    assert!(lit_pixels(&cpu) > 0);
+    run(&mut cpu, 1);
+    assert_eq!(lit_pixels(&cpu), 0);
+    assert_eq!(cpu.get_pc(), 0x206);
+}
+
+#[test]
+fn call_2nnn_and_return_00ee() {
+    let mut cpu = cpu(&[0x2206, 0x6001, 0x1204, 0x00EE]);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x206);
+    assert_eq!(cpu.get_sp(), 1);
+    assert_eq!(cpu.get_stack()[0], 0x200);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x202);
+    assert_eq!(cpu.get_sp(), 0);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_v()[0], 1);
+}
+
+#[test]
+fn return_with_empty_stack_fails() {
+    let mut cpu = cpu(&[0x00EE]);
+    assert!(matches!(cpu.emulate_cycle(), Err(Chip8Error::StackUnderflow { pc: 0x200 })));
+}
+
+#[test]
+fn call_overflows_after_sixteen_levels() {
+    // calls itself forever
+    let mut cpu = cpu(&[0x2200]);
+    run(&mut cpu, 16);
+    assert!(matches!(cpu.emulate_cycle(), Err(Chip8Error::StackOverflow { pc: 0x200 })));
+}
+
+#[test]
+fn sys_0nnn_is_unknown() {
+    let mut cpu = cpu(&[0x0123]);
+    assert!(matches!(cpu.emulate_cycle(), Err(Chip8Error::UnknownOpcode { opcode: 0x0123, pc: 0x200 })));
+}
+
+#[test]
+fn undefined_opcodes_are_unknown() {
+    for opcode in [0x5001, 0x800F, 0x9001, 0xE000, 0xF0FF, 0xF100] {
+        let mut cpu = cpu(&[opcode]);
+        assert!(matches!(cpu.emulate_cycle(), Err(Chip8Error::UnknownOpcode { .. })), "{:#06x}", opcode);
+    }
+}
+
+#[test]
+fn scroll_down_00cn() {
+    let mut cpu = cpu(&[0xA300, 0xD011, 0x00C3]);
+    cpu.set_memory(0x300, 0x80).unwrap();
+    run(&mut cpu, 3);
+    assert_eq!(pixel(&cpu, 0, 0), 0);
+    assert_eq!(pixel(&cpu, 0, 3), 1);
+}
+
+#[test]
+fn scroll_right_00fb_and_left_00fc() {
+    let mut cpu = cpu(&[0xA300, 0x6008, 0xD011, 0x00FB, 0x00FC, 0x00FC]);
+    cpu.set_memory(0x300, 0x80).unwrap();
+    run(&mut cpu, 4);
+    assert_eq!(pixel(&cpu, 12, 0), 1);
+    run(&mut cpu, 2);
+    assert_eq!(pixel(&cpu, 4, 0), 1);
+    assert_eq!(lit_pixels(&cpu), 1);
+}
+
+#[test]
+fn exit_00fd() {
+    let mut cpu = cpu(&[0x00FD, 0x6001]);
+    run(&mut cpu, 2);
+    assert!(cpu.has_exited());
+    assert_eq!(cpu.get_v()[0], 0);
+}
+
+#[test]
+fn hires_00ff_and_lores_00fe() {
+    let mut cpu = cpu(&[0x00FF, 0x00FE]);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_resolution(), (128, 64));
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_resolution(), (64, 32));
+}
+
+#[test]
+fn jump_1nnn() {
+    let mut cpu = cpu(&[0x1ABC]);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0xABC);
+}
+
+#[test]
+fn skip_if_equal_3xnn() {
+    let mut cpu = cpu(&[0x6042, 0x3042, 0x0000, 0x3041]);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_pc(), 0x206);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x208);
+}
+
+#[test]
+fn skip_if_not_equal_4xnn() {
+    let mut cpu = cpu(&[0x6042, 0x4041, 0x0000, 0x4042]);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_pc(), 0x206);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x208);
+}
+
+#[test]
+fn skip_if_registers_equal_5xy0() {
+    let mut cpu = cpu(&[0x6005, 0x6105, 0x5010, 0x0000, 0x5020]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_pc(), 0x208);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x20A);
+}
+
+#[test]
+fn skip_steps_over_the_whole_f000_instruction() {
+    let mut cpu = cpu(&[0x3000, 0xF000, 0x0300, 0x6001]);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x206);
+}
+
+#[test]
+fn save_range_5xy2_and_load_range_5xy3() {
+    let mut cpu = cpu_with(Quirks::XO_CHIP, Mode::XoChip, &[0x6101, 0x6202, 0x6303, 0xA300, 0x5132, 0x5312, 0xA310, 0x5233]);
+    cpu.set_memory(0x310, 0x77).unwrap();
+    run(&mut cpu, 5);
+    assert_eq!(&cpu.get_memory()[0x300..0x303], &[1, 2, 3]);
+    run(&mut cpu, 1);
+    assert_eq!(&cpu.get_memory()[0x300..0x303], &[3, 2, 1]);
+    assert_eq!(cpu.get_i(), 0x300);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_v()[2], 0x77);
+    assert_eq!(cpu.get_v()[3], 0x00);
+}
+
+#[test]
+fn set_6xnn_and_add_7xnn() {
+    let mut cpu = cpu(&[0x6AFE, 0x7A03]);
+    cpu.set_v(VF, 9).unwrap();
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_v()[0xA], 0xFE);
+    run(&mut cpu, 1);
+    // wraps and leaves VF alone
+    assert_eq!(cpu.get_v()[0xA], 0x01);
+    assert_eq!(cpu.get_v()[VF], 9);
+}
+
+#[test]
+fn copy_8xy0() {
+    let mut cpu = cpu(&[0x6133, 0x8010]);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_v()[0], 0x33);
+}
+
+#[test]
+fn logic_8xy1_8xy2_8xy3() {
+    let mut cpu = cpu(&[0x600C, 0x610A, 0x8011, 0x620C, 0x8212, 0x630C, 0x8313]);
+    cpu.set_v(VF, 7).unwrap();
+    run(&mut cpu, 7);
+    assert_eq!(cpu.get_v()[0], 0x0E);
+    assert_eq!(cpu.get_v()[2], 0x08);
+    assert_eq!(cpu.get_v()[3], 0x06);
+    assert_eq!(cpu.get_v()[VF], 7);
+}
+
+#[test]
+fn logic_resets_vf_with_the_vip_quirk() {
+    for opcode in [0x8011, 0x8012, 0x8013] {
+        let mut cpu = cpu_with(Quirks::COSMAC_VIP, Mode::Chip8, &[opcode]);
+        cpu.set_v(VF, 7).unwrap();
+        run(&mut cpu, 1);
+        assert_eq!(cpu.get_v()[VF], 0, "{:#06x}", opcode);
+    }
+}
+
+#[test]
+fn add_8xy4_sets_carry() {
+    let mut cpu = cpu(&[0x60F0, 0x6120, 0x8014, 0x6201, 0x8024]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_v()[0], 0x10);
+    assert_eq!(cpu.get_v()[VF], 1);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_v()[0], 0x11);
+    assert_eq!(cpu.get_v()[VF], 0);
+}
+
+#[test]
+fn subtract_8xy5_sets_not_borrow() {
+    let mut cpu = cpu(&[0x600A, 0x6103, 0x8015, 0x620A, 0x8025]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_v()[0], 7);
+    assert_eq!(cpu.get_v()[VF], 1);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_v()[0], 0xFD);
+    assert_eq!(cpu.get_v()[VF], 0);
+}
+
+#[test]
+fn subtract_reversed_8xy7_sets_not_borrow() {
+    let mut cpu = cpu(&[0x6003, 0x610A, 0x8017, 0x6201, 0x8027]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_v()[0], 7);
+    assert_eq!(cpu.get_v()[VF], 1);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_v()[0], 0xFA);
+    assert_eq!(cpu.get_v()[VF], 0);
+}
+
+#[test]
+fn arithmetic_flag_wins_over_vf_result() {
+    // VF as the destination ends up holding the flag, not the result
+    let mut cpu = cpu(&[0x6FFF, 0x6101, 0x8F14, 0x6F05, 0x8F15, 0x6F05, 0x8F17]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_v()[VF], 1);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_v()[VF], 1);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_v()[VF], 0);
+}
+
+#[test]
+fn shift_right_8xy6() {
+    let mut cpu = cpu(&[0x6005, 0x6110, 0x8016]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_v()[0], 0x02);
+    assert_eq!(cpu.get_v()[VF], 1);
+
+    let mut cpu = cpu_with(Quirks::COSMAC_VIP, Mode::Chip8, &[0x6005, 0x6110, 0x8016]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_v()[0], 0x08);
+    assert_eq!(cpu.get_v()[VF], 0);
+}
+
+#[test]
+fn shift_left_8xye() {
+    let mut cpu = cpu(&[0x6081, 0x6101, 0x801E]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_v()[0], 0x02);
+    assert_eq!(cpu.get_v()[VF], 1);
+
+    let mut cpu = cpu_with(Quirks::COSMAC_VIP, Mode::Chip8, &[0x6081, 0x6101, 0x801E]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_v()[0], 0x02);
+    assert_eq!(cpu.get_v()[VF], 0);
+}
+
+#[test]
+fn skip_if_registers_not_equal_9xy0() {
+    let mut cpu = cpu(&[0x6001, 0x9010, 0x0000, 0x9000]);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_pc(), 0x206);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x208);
+}
+
+#[test]
+fn set_index_annn() {
+    let mut cpu = cpu(&[0xA123]);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_i(), 0x123);
+}
+
+#[test]
+fn jump_offset_bnnn() {
+    let mut cpu = cpu(&[0x6004, 0x6208, 0xB300]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_pc(), 0x304);
+
+    // BXNN jumps relative to VX instead
+    let mut cpu = cpu_with(Quirks::SUPER_CHIP, Mode::SuperChip, &[0x6004, 0x6308, 0xB300]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_pc(), 0x308);
+}
+
+#[test]
+fn random_cxnn_masks_the_random_byte() {
+    let mut cpu = cpu(&[0x6000, 0xC00F, 0x61FF, 0xC1F0]);
+    cpu.set_random_source(Box::new(FixedRandom(0x5A)));
+    run(&mut cpu, 2);
+    // assigned, not combined with the old value
+    assert_eq!(cpu.get_v()[0], 0x0A);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_v()[1], 0x50);
+}
+
+#[test]
+fn draw_dxyn_toggles_and_reports_collisions() {
+    let mut cpu = cpu(&[0x6002, 0x6103, 0xA300, 0xD012, 0xD012]);
+    cpu.set_memory(0x300, 0xC0).unwrap();
+    cpu.set_memory(0x301, 0x80).unwrap();
+    run(&mut cpu, 4);
+    assert_eq!(lit_pixels(&cpu), 3);
+    assert_eq!(pixel(&cpu, 2, 3), 1);
+    assert_eq!(pixel(&cpu, 3, 3), 1);
+    assert_eq!(pixel(&cpu, 2, 4), 1);
+    assert_eq!(cpu.get_v()[VF], 0);
+    assert!(cpu.check_draw_sema());
+    run(&mut cpu, 1);
+    assert_eq!(lit_pixels(&cpu), 0);
+    assert_eq!(cpu.get_v()[VF], 1);
+}
+
+#[test]
+fn draw_dxyn_clips_or_wraps_at_the_edge() {
+    let program: [u16;4] = [0x603C, 0x611F, 0xA300, 0xD012];
+
+    let mut cpu = cpu_with(Quirks::COSMAC_VIP, Mode::Chip8, &program);
+    cpu.set_memory(0x300, 0xFF).unwrap();
+    cpu.set_memory(0x301, 0xFF).unwrap();
+    run(&mut cpu, 4);
+    assert_eq!(lit_pixels(&cpu), 4);
+
+    let mut cpu = cpu_with(Quirks::MODERN, Mode::Chip8, &program);
+    cpu.set_memory(0x300, 0xFF).unwrap();
+    cpu.set_memory(0x301, 0xFF).unwrap();
+    run(&mut cpu, 4);
+    assert_eq!(lit_pixels(&cpu), 16);
+    assert_eq!(pixel(&cpu, 0, 0), 1);
+}
+
+#[test]
+fn draw_dxy0_draws_a_16x16_sprite() {
+    let mut cpu = cpu_with(Quirks::SUPER_CHIP, Mode::SuperChip, &[0x00FF, 0xA300, 0xD000]);
+    for offset in 0..32 {
+        cpu.set_memory(0x300 + offset, 0xFF).unwrap();
+    }
+    run(&mut cpu, 3);
+    assert_eq!(lit_pixels(&cpu), 256);
+    assert_eq!(pixel(&cpu, 15, 15), 1);
+}
+
+#[test]
+fn draw_dxy0_in_lores_depends_on_the_mode() {
+    // SCHIP 1.1 draws 8x16 outside hires
+    let mut cpu = cpu_with(Quirks::SUPER_CHIP, Mode::SuperChip, &[0xA300, 0xD000]);
+    for offset in 0..32 {
+        cpu.set_memory(0x300 + offset, 0xFF).unwrap();
+    }
+    run(&mut cpu, 2);
+    assert_eq!(lit_pixels(&cpu), 128);
+    assert_eq!(pixel(&cpu, 7, 15), 1);
+    assert_eq!(pixel(&cpu, 8, 0), 0);
+
+    // the VIP draws nothing and clears VF
+    let mut cpu = cpu_with(Quirks::COSMAC_VIP, Mode::Chip8, &[0x6F01, 0xA300, 0xD000]);
+    for offset in 0..32 {
+        cpu.set_memory(0x300 + offset, 0xFF).unwrap();
+    }
+    run(&mut cpu, 3);
+    assert_eq!(lit_pixels(&cpu), 0);
+    assert_eq!(cpu.get_v()[VF], 0);
+
+    // Octo draws 16x16 either way
+    let mut cpu = cpu_with(Quirks::XO_CHIP, Mode::XoChip, &[0xA300, 0xD000]);
+    for offset in 0..32 {
+        cpu.set_memory(0x300 + offset, 0xFF).unwrap();
+    }
+    run(&mut cpu, 2);
+    assert_eq!(lit_pixels(&cpu), 256);
+}
+
+#[test]
+fn skip_if_key_ex9e_and_not_key_exa1() {
+    let mut keys: [u8;16] = [0;16];
+    keys[5] = 1;
+
+    let mut cpu = cpu(&[0x6005, 0xE09E, 0x0000, 0xE0A1, 0x6006, 0xE0A1]);
+    cpu.update_keystate(&keys);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_pc(), 0x206);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x208);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_pc(), 0x20E);
+}
+
+#[test]
+fn set_index_long_f000() {
+    let mut cpu = cpu_with(Quirks::XO_CHIP, Mode::XoChip, &[0xF000, 0xBEEF, 0x6001]);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_i(), 0xBEEF);
+    assert_eq!(cpu.get_pc(), 0x204);
+}
+
+#[test]
+fn pc_wraps_at_the_end_of_xo_chip_memory() {
+    // 00E0 at FFFE runs into 0000
+    let mut cpu = cpu_with(Quirks::XO_CHIP, Mode::XoChip, &[]);
+    let poke = |cpu: &mut Chip8, address: usize, bytes: &[u8]| {
+        for (offset, byte) in bytes.iter().enumerate() {
+            cpu.set_memory(address + offset, *byte).unwrap();
+        }
+    };
+    poke(&mut cpu, 0xFFFE, &[0x00, 0xE0]);
+    cpu.set_pc(0xFFFE);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x0000);
+
+    // a skip at FFFE steps over the instruction at 0000
+    poke(&mut cpu, 0xFFFE, &[0x30, 0x00]);
+    cpu.set_pc(0xFFFE);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x0002);
+
+    // F000 NNNN at FFFE reads its address from 0000
+    poke(&mut cpu, 0xFFFE, &[0xF0, 0x00]);
+    poke(&mut cpu, 0x0000, &[0x12, 0x34]);
+    cpu.set_pc(0xFFFE);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_i(), 0x1234);
+    assert_eq!(cpu.get_pc(), 0x0002);
+
+    // returning to a call made at FFFE
+    poke(&mut cpu, 0x0300, &[0x00, 0xEE]);
+    poke(&mut cpu, 0xFFFE, &[0x23, 0x00]);
+    cpu.set_pc(0xFFFE);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_pc(), 0x0000);
+}
+
+#[test]
+fn select_planes_fn01() {
+    // plane 2 only, so the drawn pixel has value 2
+    let mut cpu = cpu_with(Quirks::XO_CHIP, Mode::XoChip, &[0xF201, 0xA300, 0xD011]);
+    cpu.set_memory(0x300, 0x80).unwrap();
+    run(&mut cpu, 3);
+    assert_eq!(pixel(&cpu, 0, 0), 2);
+}
+
+#[test]
+fn load_audio_f002_and_pitch_fx3a() {
+    let mut cpu = cpu_with(Quirks::XO_CHIP, Mode::XoChip, &[0xA300, 0xF002, 0x6070, 0xF03A]);
+    for offset in 0..16 {
+        cpu.set_memory(0x300 + offset, offset as u8).unwrap();
+    }
+    run(&mut cpu, 2);
+    let expected: Vec<u8> = (0..16).collect();
+    assert_eq!(cpu.get_audio_pattern().to_vec(), expected);
+    let default_rate: f32 = cpu.get_playback_rate();
+    run(&mut cpu, 2);
+    assert!(cpu.get_playback_rate() > default_rate);
+}
+
+#[test]
+fn timers_fx07_fx15_fx18() {
+    let mut cpu = cpu(&[0x6030, 0xF015, 0xF018, 0xF107]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_delay_timer(), 0x30);
+    assert_eq!(cpu.get_sound_timer(), 0x30);
+    assert!(cpu.is_sound_playing());
+    cpu.tick_timers();
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_v()[1], 0x2F);
+}
+
+#[test]
+fn wait_key_fx0a_waits_for_release() {
+    let mut cpu = cpu(&[0xF30A]);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.get_pc(), 0x200);
+    assert!(cpu.is_waiting_for_key());
+
+    let mut keys: [u8;16] = [0;16];
+    keys[0xB] = 1;
+    cpu.update_keystate(&keys);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x200);
+
+    cpu.update_keystate(&[0;16]);
+    run(&mut cpu, 1);
+    assert_eq!(cpu.get_pc(), 0x202);
+    assert_eq!(cpu.get_v()[3], 0xB);
+}
+
+#[test]
+fn add_index_fx1e() {
+    let mut cpu = cpu(&[0xA0FF, 0x6002, 0xF01E]);
+    run(&mut cpu, 3);
+    assert_eq!(cpu.get_i(), 0x101);
+}
+
+#[test]
+fn font_characters_fx29_fx30() {
+    let mut cpu = cpu_with(Quirks::SUPER_CHIP, Mode::SuperChip, &[0x600A, 0xF029, 0xF030]);
+    run(&mut cpu, 2);
+    let small: u16 = cpu.get_i();
+    assert_eq!(&cpu.get_memory()[small as usize..small as usize + 5], &[0xF0, 0x90, 0xF0, 0x90, 0x90]);
+    run(&mut cpu, 1);
+    let big: u16 = cpu.get_i();
+    assert_ne!(big, small);
+    assert!(big as usize + 10 <= 0x200);
+}
+
+#[test]
+fn bcd_fx33() {
+    let mut cpu = cpu(&[0x60FE, 0xA300, 0xF033]);
+    run(&mut cpu, 3);
+    assert_eq!(&cpu.get_memory()[0x300..0x303], &[2, 5, 4]);
+}
+
+#[test]
+fn store_fx55_and_load_fx65() {
+    let mut cpu = cpu(&[0x6011, 0x6122, 0x6233, 0xA300, 0xF255, 0xA300, 0xF165]);
+    run(&mut cpu, 5);
+    assert_eq!(&cpu.get_memory()[0x300..0x304], &[0x11, 0x22, 0x33, 0x00]);
+    assert_eq!(cpu.get_i(), 0x300);
+    cpu.set_v(0, 0).unwrap();
+    cpu.set_v(1, 0).unwrap();
+    cpu.set_v(2, 0).unwrap();
+    run(&mut cpu, 2);
+    assert_eq!(&cpu.get_v()[..3], &[0x11, 0x22, 0x00]);
+}
+
+#[test]
+fn load_store_index_increment_quirks() {
+    let increments: [(Quirks, u16);3] = [(Quirks::MODERN, 0x300), (Quirks::CHIP_48, 0x302), (Quirks::COSMAC_VIP, 0x303)];
+    for (quirks, expected) in increments.iter() {
+        let mut cpu = cpu_with(*quirks, Mode::Chip8, &[0xA300, 0xF255]);
+        run(&mut cpu, 2);
+        assert_eq!(cpu.get_i(), *expected, "{:?}", quirks.load_store_increment);
+    }
+    assert_eq!(Quirks::COSMAC_VIP.load_store_increment, IndexIncrement::XPlusOne);
+}
+
+#[test]
+fn flags_fx75_fx85() {
+    let mut cpu = cpu_with(Quirks::SUPER_CHIP, Mode::SuperChip, &[0x6007, 0x6109, 0xF175, 0x6000, 0x6100, 0xF185]);
+    run(&mut cpu, 6);
+    assert_eq!(&cpu.get_v()[..2], &[7, 9]);
+
+    // SCHIP only has eight flags
+    let mut cpu = cpu_with(Quirks::SUPER_CHIP, Mode::SuperChip, &[0xF875]);
+    assert!(matches!(cpu.emulate_cycle(), Err(Chip8Error::UnknownOpcode { .. })));
+    let mut cpu = cpu_with(Quirks::XO_CHIP, Mode::XoChip, &[0xFF75]);
+    run(&mut cpu, 1);
+}
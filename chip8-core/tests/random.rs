@@ -0,0 +1,58 @@
+use chip8_core::chip8::{Chip8, Mode};
+use chip8_core::quirks::Quirks;
+use chip8_core::random::RandomKind;
+
+/*
+    CXNN results only depend on the generator, its seed and how many bytes
+    were drawn, which is what makes seeded runs, save states and movies
+    repeatable
+*/
+
+const KINDS: [RandomKind;2] = [RandomKind::Xorshift, RandomKind::Lcg];
+
+// CXFF into V0-V7, then back to the start
+const PROGRAM: [u16;9] = [0xC0FF, 0xC1FF, 0xC2FF, 0xC3FF, 0xC4FF, 0xC5FF, 0xC6FF, 0xC7FF, 0x1200];
+
+fn cpu(kind: RandomKind, seed: u64) -> Chip8 {
+    let mut cpu = Chip8::new(Quirks::MODERN);
+    cpu.set_mode(Mode::Chip8);
+    cpu.initialize();
+    cpu.seed_rng(seed);
+    cpu.set_random_kind(kind);
+    let rom: Vec<u8> = PROGRAM.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    cpu.load_rom(&rom).unwrap();
+    cpu
+}
+
+// the next eight random bytes, one pass through the program
+fn draw(cpu: &mut Chip8) -> Vec<u8> {
+    for _ in 0..PROGRAM.len() {
+        cpu.emulate_cycle().unwrap();
+    }
+    cpu.get_v()[..8].to_vec()
+}
+
+#[test]
+fn the_same_seed_gives_the_same_sequence() {
+    for kind in KINDS {
+        let (mut first, mut second) = (cpu(kind, 1234), cpu(kind, 1234));
+        let sequence: Vec<Vec<u8>> = (0..4).map(|_| draw(&mut first)).collect();
+        assert_eq!(sequence, (0..4).map(|_| draw(&mut second)).collect::<Vec<Vec<u8>>>(), "{:?}", kind);
+
+        let mut other = cpu(kind, 4321);
+        assert_ne!(sequence, (0..4).map(|_| draw(&mut other)).collect::<Vec<Vec<u8>>>(), "{:?}", kind);
+    }
+}
+
+#[test]
+fn save_states_keep_the_generator_position() {
+    for kind in KINDS {
+        let mut cpu = cpu(kind, 99);
+        draw(&mut cpu);
+        let state: Vec<u8> = cpu.save_state();
+        let expected: Vec<Vec<u8>> = (0..3).map(|_| draw(&mut cpu)).collect();
+
+        cpu.load_state(&state).unwrap();
+        assert_eq!((0..3).map(|_| draw(&mut cpu)).collect::<Vec<Vec<u8>>>(), expected, "{:?}", kind);
+    }
+}
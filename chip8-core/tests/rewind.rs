@@ -0,0 +1,80 @@
+use chip8_core::rewind::RewindBuffer;
+
+/*
+    States go in whole and come back out of the XOR deltas byte for byte,
+    newest first, starting with the one before the frame on screen
+*/
+
+// a state of size bytes where frame changes a few of them
+fn state(size: usize, frame: u8) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..size).map(|index| (index % 251) as u8).collect();
+    state[0] = frame;
+    state[size / 2] ^= frame.wrapping_mul(3);
+    state[size - 1] = !frame;
+    state
+}
+
+#[test]
+fn pop_steps_back_from_the_frame_on_screen() {
+    let mut buffer = RewindBuffer::new(10);
+    for frame in 0..4 {
+        buffer.push(state(64, frame));
+    }
+    // frame 3 is on screen, going back shows 2, then 1, then 0
+    assert_eq!(buffer.pop(), Some(state(64, 2)));
+    assert_eq!(buffer.pop(), Some(state(64, 1)));
+    assert_eq!(buffer.pop(), Some(state(64, 0)));
+    assert_eq!(buffer.pop(), None);
+    // the oldest state is still what is on screen
+    assert_eq!(buffer.len(), 1);
+
+    // running on from a rewound frame records after it
+    buffer.push(state(64, 7));
+    assert_eq!(buffer.pop(), Some(state(64, 0)));
+}
+
+#[test]
+fn the_oldest_states_are_dropped_at_capacity() {
+    let mut buffer = RewindBuffer::new(3);
+    for frame in 0..6 {
+        buffer.push(state(64, frame));
+    }
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(buffer.pop(), Some(state(64, 4)));
+    assert_eq!(buffer.pop(), Some(state(64, 3)));
+    assert_eq!(buffer.pop(), None);
+}
+
+#[test]
+fn deltas_round_trip_long_runs_and_small_changes() {
+    // runs of equal and of different bytes longer than a u16 count
+    let size: usize = 200_000;
+    let first: Vec<u8> = vec![0; size];
+    let mut second: Vec<u8> = first.clone();
+    for byte in &mut second[10..70_010] {
+        *byte = 0xA5;
+    }
+    second[size - 1] = 1;
+    let mut third: Vec<u8> = second.clone();
+    third[150_000] = 0xFF;
+
+    let mut buffer = RewindBuffer::new(5);
+    buffer.push(first.clone());
+    buffer.push(second.clone());
+    buffer.push(third.clone());
+    // one whole state plus the deltas, the small change costs a few bytes
+    assert!(buffer.memory_usage() < size + 70_100, "{}", buffer.memory_usage());
+
+    assert_eq!(buffer.pop(), Some(second));
+    assert_eq!(buffer.pop(), Some(first));
+}
+
+#[test]
+fn states_of_another_size_start_over() {
+    let mut buffer = RewindBuffer::new(10);
+    buffer.push(state(64, 0));
+    buffer.push(state(64, 1));
+    buffer.push(state(128, 2));
+    assert_eq!(buffer.len(), 1);
+    assert_eq!(buffer.pop(), None);
+}
@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use chip8_core::chip8::{Chip8, Mode};
+use chip8_core::headless::{HeadlessRunner, StopCondition, StopReason};
+use chip8_core::quirks::Quirks;
+
+/*
+    Runs the bundled ROMs headlessly and compares the final screen with a
+    known good one. The golden hashes are FNV-1a of HeadlessRunner::framebuffer_text,
+    after a fixed number of frames with no input and the random generator seeded
+    with 0. When one changes on purpose, `chip8-headless ROM --frames N --text -`
+    shows the new screen to check before updating the hash.
+
+    Community test ROMs (corax+, flags, quirks and friends) can't be shipped
+    here, see tests/fixtures/README.md for running them.
+*/
+
+const PROGRAMS: &str = "../src/programs";
+const FIXTURES: &str = "tests/fixtures";
+
+// ROM, frames to run, hash of the screen afterwards
+const GOLDEN: [(&str, u64, u64);8] = [
+    ("BRIX", 120, 0x5f160e825b6291f9),
+    ("INVADERS", 300, 0xbc2b1a62c6f4581a),
+    ("KALEID", 120, 0xa39c70f98c2e4ef9),
+    ("MAZE", 60, 0x420cfee50a4927d5),
+    ("PONG", 120, 0x77dd1dbb6d9558f4),
+    ("TETRIS", 120, 0x6199c6be8c918ccb),
+    ("UFO", 120, 0xca5cfc0bca1b36c5),
+    ("WIPEOFF", 120, 0x095777420d09c4cd),
+];
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn cpu(path: &Path, quirks: Quirks) -> Chip8 {
+    let mut cpu = Chip8::new(quirks);
+    cpu.set_mode(Mode::Chip8);
+    cpu.seed_rng(0);
+    cpu.initialize();
+    cpu.load(path.display().to_string()).unwrap();
+    cpu
+}
+
+fn runner(cpu: Chip8, frames: u64) -> HeadlessRunner {
+    let mut runner = HeadlessRunner::new(cpu);
+    runner.add_stop_condition(StopCondition::Frames(frames));
+    runner
+}
+
+#[test]
+fn bc_test_passes() {
+    let mut runner = runner(cpu(&Path::new(PROGRAMS).join("BC_TEST"), Quirks::default()), 600);
+    runner.add_stop_condition(StopCondition::PcLoop);
+    let reason: StopReason = runner.run();
+    assert!(matches!(reason, StopReason::PcLoop(_)), "stopped with {}", reason);
+
+    // "BON" on success, otherwise the number of the failed check
+    let screen: String = runner.framebuffer_text();
+    let rows: Vec<&str> = screen.lines().skip(11).take(8).collect();
+    let expected: [&str;8] = [
+        ".....................####.....####...#....#.....................",
+        ".....................#...#...#....#..##...#.....................",
+        ".....................#...#...#....#..#.#..#.....................",
+        ".....................####....#....#..#..#.#.....................",
+        ".....................#...#...#....#..#...##.....................",
+        ".....................#...#...#....#..#....#.....................",
+        ".....................#...#...#....#..#....#.....................",
+        ".....................####.....####...#....#.....................",
+    ];
+    assert_eq!(rows, expected, "BC_TEST failed, screen:\n{}", screen);
+}
+
+#[test]
+fn bundled_roms_match_golden_screens() {
+    let mut mismatches: Vec<String> = Vec::new();
+    for (name, frames, golden) in GOLDEN.iter() {
+        let mut runner = runner(cpu(&Path::new(PROGRAMS).join(name), Quirks::default()), *frames);
+        let reason: StopReason = runner.run();
+        assert!(matches!(reason, StopReason::FrameLimit), "{} stopped with {}", name, reason);
+
+        let screen: String = runner.framebuffer_text();
+        let hash: u64 = fnv1a(screen.as_bytes());
+        if hash != *golden {
+            mismatches.push(format!("{} after {} frames hashed {:#018x}, expected {:#018x}:\n{}", name, frames, hash, golden, screen));
+        }
+    }
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+#[test]
+fn fixture_roms_match_expected_screens() {
+    let mut roms: Vec<PathBuf> = match std::fs::read_dir(FIXTURES) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "ch8"))
+            .collect(),
+        Err(_) => return,
+    };
+    roms.sort();
+
+    for rom in roms {
+        let expected_path: PathBuf = rom.with_extension("txt");
+        let mut cpu: Chip8 = cpu(&rom, Quirks::COSMAC_VIP);
+        // the test suites read 0x1FF to pick CHIP-8 without asking for a key
+        cpu.set_memory(0x1FF, 1).unwrap();
+        let mut runner = runner(cpu, 600);
+        runner.add_stop_condition(StopCondition::PcLoop);
+        let reason: StopReason = runner.run();
+        let screen: String = runner.framebuffer_text();
+
+        let expected: String = std::fs::read_to_string(&expected_path).unwrap_or_else(|_| {
+            panic!("{} has no {} to compare with, its screen after the run ({}):\n{}", rom.display(), expected_path.display(), reason, screen)
+        });
+        assert_eq!(screen, expected, "{} doesn't match {} ({})", rom.display(), expected_path.display(), reason);
+    }
+}
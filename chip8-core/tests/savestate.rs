@@ -0,0 +1,100 @@
+use chip8_core::chip8::{Chip8, Mode};
+use chip8_core::error::Chip8Error;
+use chip8_core::quirks::Quirks;
+
+/*
+    Save states restore the machine exactly, and anything that isn't a whole
+    state of this rom and mode is refused without touching the machine
+*/
+
+const BRIX: &str = "../src/programs/BRIX";
+const PONG: &str = "../src/programs/PONG";
+
+fn cpu(path: &str) -> Chip8 {
+    let mut cpu = Chip8::new(Quirks::MODERN);
+    cpu.set_mode(Mode::Chip8);
+    cpu.initialize();
+    cpu.seed_rng(0);
+    cpu.load_rom(&std::fs::read(path).unwrap()).unwrap();
+    cpu
+}
+
+fn run_frames(cpu: &mut Chip8, frames: usize) {
+    for _ in 0..frames {
+        cpu.run_frame().unwrap();
+    }
+}
+
+fn assert_refused(cpu: &mut Chip8, state: &[u8], reason: &str) {
+    let before: Vec<u8> = cpu.save_state();
+    match cpu.load_state(state) {
+        Err(Chip8Error::BadSaveState { reason: found }) => assert!(found.contains(reason), "{}", found),
+        other => panic!("expected a bad save state ({}), got {:?}", reason, other),
+    }
+    assert!(cpu.save_state() == before, "a refused state changed the machine");
+}
+
+#[test]
+fn a_restored_state_runs_on_identically() {
+    let mut cpu = cpu(BRIX);
+    run_frames(&mut cpu, 30);
+    let state: Vec<u8> = cpu.save_state();
+    run_frames(&mut cpu, 60);
+    let (gfx, pc, v, frames) = (cpu.get_gfx().to_vec(), cpu.get_pc(), cpu.get_v(), cpu.get_frame_count());
+
+    cpu.load_state(&state).unwrap();
+    assert!(cpu.save_state() == state);
+    assert_eq!(cpu.get_frame_count(), 30);
+    run_frames(&mut cpu, 60);
+    assert_eq!((cpu.get_gfx().to_vec(), cpu.get_pc(), cpu.get_v(), cpu.get_frame_count()), (gfx, pc, v, frames));
+
+    // a fresh machine with the same rom takes it too
+    let mut other = self::cpu(BRIX);
+    other.load_state(&state).unwrap();
+    assert!(other.save_state() == state);
+}
+
+#[test]
+fn bad_magic_and_version_are_refused() {
+    let mut cpu = cpu(BRIX);
+    run_frames(&mut cpu, 10);
+    let state: Vec<u8> = cpu.save_state();
+    run_frames(&mut cpu, 10);
+
+    let mut bad_magic: Vec<u8> = state.clone();
+    bad_magic[..4].copy_from_slice(b"C8MV");
+    assert_refused(&mut cpu, &bad_magic, "not a save state");
+
+    let mut bad_version: Vec<u8> = state.clone();
+    bad_version[5] = bad_version[5].wrapping_add(1);
+    assert_refused(&mut cpu, &bad_version, "version");
+}
+
+#[test]
+fn states_of_another_rom_or_mode_are_refused() {
+    let state: Vec<u8> = cpu(PONG).save_state();
+    assert_refused(&mut cpu(BRIX), &state, "different rom");
+
+    let mut schip = Chip8::new(Quirks::SUPER_CHIP);
+    schip.set_mode(Mode::SuperChip);
+    schip.initialize();
+    schip.load_rom(&std::fs::read(BRIX).unwrap()).unwrap();
+    assert_refused(&mut cpu(BRIX), &schip.save_state(), "different mode");
+}
+
+#[test]
+fn truncated_states_are_refused() {
+    let mut cpu = cpu(BRIX);
+    run_frames(&mut cpu, 10);
+    let state: Vec<u8> = cpu.save_state();
+    run_frames(&mut cpu, 10);
+
+    for length in [0, 3, 5, 14, 15, 40, state.len() - 1] {
+        let before: Vec<u8> = cpu.save_state();
+        assert!(matches!(cpu.load_state(&state[..length]), Err(Chip8Error::BadSaveState { .. })), "length {}", length);
+        assert!(cpu.save_state() == before, "length {} changed the machine", length);
+    }
+    let mut longer: Vec<u8> = state.clone();
+    longer.push(0);
+    assert_refused(&mut cpu, &longer, "wrong size");
+}
@@ -0,0 +1,66 @@
+use chip8_core::debugger::Register;
+use chip8_core::trace_diff::{diff, load, parse_text, report, DiffOptions, Difference, TraceEntry};
+
+const OURS: &str = "\
+0 0200 6005 LD V0, #05 ; V0=05
+0 0202 A300 LD I, #300 ; I=0300
+0 0204 F033 LD B, V0 ; [0300]=00 [0301]=00 [0302]=05
+1 0206 8014 ADD V0, V1 ; VF=00
+";
+
+fn entries(text: &str) -> Vec<TraceEntry> {
+    parse_text(text).unwrap()
+}
+
+#[test]
+fn reference_format_with_full_register_dumps_matches() {
+    let reference: &str = "\
+# another emulator, no frame counter
+- 0x0200 0x6005 ld v0, 5 ; PC=0202 V0=05 I=0000
+- 0x0202 0xa300 ld i, 0x300 ; PC=0204 V0=05 I=0300
+
+- 0x0204 0xf033 bcd v0 ; PC=0206 v0=05 i=0300 [0300]=00 [0301]=00 [0302]=05
+- 0x0206 0x8014 add v0, v1 ; PC=0208 V0=05 I=0300 VF=00
+";
+    assert_eq!(diff(&entries(OURS), &entries(reference), &DiffOptions::default()), None);
+}
+
+#[test]
+fn first_divergence_reports_register_and_opcode() {
+    let reference: String = OURS.replace("0206 8014 ADD V0, V1 ; VF=00", "0206 8015 SUB V0, V1 ; V0=05 VF=01").replace("1 0206", "2 0206");
+    let divergence = diff(&entries(OURS), &entries(&reference), &DiffOptions::default()).unwrap();
+    assert_eq!(divergence.index, 3);
+    assert_eq!(divergence.differences, vec![
+        Difference::Frame(1, 2),
+        Difference::Opcode(0x8014, 0x8015),
+        Difference::Register(Register::V(15), 0, 1),
+    ]);
+    assert_eq!(divergence.registers_before[0], 5);
+
+    let options = DiffOptions { compare_frames: false, ..DiffOptions::default() };
+    let divergence = diff(&entries(OURS), &entries(&reference), &options).unwrap();
+    assert!(!divergence.differences.contains(&Difference::Frame(1, 2)));
+    let text: String = report(&entries(OURS), &entries(&reference), &divergence, 1);
+    assert!(text.starts_with("traces diverge at instruction 3 (frame 1, pc 0206, 8014 ADD V0, V1)"), "{}", text);
+    assert!(text.contains("opcode: ours 8014 (ADD V0, V1), reference 8015 (SUB V0, V1)"), "{}", text);
+}
+
+#[test]
+fn memory_writes_and_length_are_compared() {
+    let reference: String = OURS.replace("[0302]=05", "[0302]=06");
+    let divergence = diff(&entries(OURS), &entries(&reference), &DiffOptions::default()).unwrap();
+    assert_eq!(divergence.index, 2);
+    let options = DiffOptions { compare_memory: false, ..DiffOptions::default() };
+    assert_eq!(diff(&entries(OURS), &entries(&reference), &options), None);
+
+    let short: Vec<TraceEntry> = entries(OURS).into_iter().take(2).collect();
+    let divergence = diff(&entries(OURS), &short, &DiffOptions::default()).unwrap();
+    assert_eq!((divergence.index, divergence.differences), (2, vec![Difference::Length(4, 2)]));
+}
+
+#[test]
+fn bad_lines_name_their_line_number() {
+    let error = load(b"0 0200 6005\n\n0 0202 A300 ; Q=1\n").unwrap_err();
+    assert_eq!(error.to_string(), "cannot read trace: line 3: unknown register 'Q'");
+    assert!(load(b"C8TR\x00\x01\x02").is_err());
+}
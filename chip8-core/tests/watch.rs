@@ -0,0 +1,89 @@
+use chip8_core::chip8::Chip8;
+use chip8_core::quirks::Quirks;
+use chip8_core::watch::{Access, WatchAction, WatchHit, Watchpoint};
+
+/*
+    Watchpoint syntax, and the accesses the cpu reports while running
+*/
+
+fn cpu(program: &[u16]) -> Chip8 {
+    let mut cpu = Chip8::new(Quirks::default());
+    cpu.initialize();
+    let rom: Vec<u8> = program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect();
+    cpu.load_rom(&rom).unwrap();
+    cpu
+}
+
+fn run(cpu: &mut Chip8, cycles: usize) {
+    for _ in 0..cycles {
+        cpu.emulate_cycle().unwrap();
+    }
+}
+
+#[test]
+fn parse_ranges_kinds_and_actions() {
+    let watchpoint = |start, end, fetch, read, write, action| Watchpoint { start, end, fetch, read, write, action };
+    assert_eq!(Watchpoint::parse("3a0"), Ok(watchpoint(0x3A0, 0x3A0, false, true, true, WatchAction::Break)));
+    assert_eq!(Watchpoint::parse("0x300-30f w"), Ok(watchpoint(0x300, 0x30F, false, false, true, WatchAction::Break)));
+    assert_eq!(Watchpoint::parse("200-2ff x log"), Ok(watchpoint(0x200, 0x2FF, true, false, false, WatchAction::Log)));
+    assert_eq!(Watchpoint::parse("3a0:rw:log"), Ok(watchpoint(0x3A0, 0x3A0, false, true, true, WatchAction::Log)));
+    assert_eq!(Watchpoint::parse("300 rwx").map(|watchpoint| watchpoint.to_string()), Ok(String::from("0x300 rwx break")));
+
+    assert!(Watchpoint::parse("").is_err());
+    assert!(Watchpoint::parse("xyz").is_err());
+    assert!(Watchpoint::parse("30f-300").unwrap_err().contains("ends before it starts"));
+    assert!(Watchpoint::parse("300 q").unwrap_err().contains("unknown watch option"));
+}
+
+#[test]
+fn matches_checks_the_range_and_the_access() {
+    let watchpoint: Watchpoint = Watchpoint::parse("300-303 w").unwrap();
+    assert!(watchpoint.matches(0x300, Access::Write));
+    assert!(watchpoint.matches(0x303, Access::Write));
+    assert!(!watchpoint.matches(0x2FF, Access::Write));
+    assert!(!watchpoint.matches(0x304, Access::Write));
+    assert!(!watchpoint.matches(0x301, Access::Read));
+    assert!(!watchpoint.matches(0x301, Access::Fetch));
+
+    let fetches: Watchpoint = Watchpoint::parse("200 x").unwrap();
+    assert!(fetches.matches(0x200, Access::Fetch));
+    assert!(!fetches.matches(0x200, Access::Read));
+}
+
+#[test]
+fn fx55_writes_in_a_watched_range_are_reported() {
+    // V0-V2 = 11 22 33, I = 300, then FX55 at 208 stores them
+    let mut cpu = cpu(&[0x6011, 0x6122, 0x6233, 0xA300, 0xF255, 0x120A]);
+    assert_eq!(cpu.watchpoints_mut().add(Watchpoint::parse("301-302 w").unwrap()), 0);
+    run(&mut cpu, 4);
+    assert_eq!(cpu.watchpoints_mut().take_break(), None);
+    assert!(cpu.watchpoints_mut().take_hits().is_empty());
+
+    run(&mut cpu, 1);
+    let hits: Vec<WatchHit> = cpu.watchpoints_mut().take_hits();
+    let summary: Vec<(u16, usize, u8, Access)> = hits.iter().map(|hit| (hit.pc, hit.address, hit.value, hit.access)).collect();
+    assert_eq!(summary, [(0x208, 0x301, 0x22, Access::Write), (0x208, 0x302, 0x33, Access::Write)]);
+    assert!(hits.iter().all(|hit| hit.watchpoint == 0 && hit.action == WatchAction::Break));
+    assert_eq!(cpu.watchpoints_mut().take_break(), Some(0));
+    // taken once
+    assert_eq!(cpu.watchpoints_mut().take_break(), None);
+}
+
+#[test]
+fn logging_watchpoints_dont_ask_to_break() {
+    let mut cpu = cpu(&[0x6011, 0xA300, 0xF055, 0x1206]);
+    cpu.watchpoints_mut().add(Watchpoint::parse("300 w log").unwrap());
+    cpu.watchpoints_mut().add(Watchpoint::parse("204 x log").unwrap());
+    run(&mut cpu, 3);
+    let hits: Vec<(usize, u16, Access)> = cpu.watchpoints_mut().take_hits().iter().map(|hit| (hit.watchpoint, hit.pc, hit.access)).collect();
+    assert_eq!(hits, [(1, 0x204, Access::Fetch), (0, 0x204, Access::Write)]);
+    assert_eq!(cpu.watchpoints_mut().take_break(), None);
+
+    // removed, nothing more is reported
+    assert!(cpu.watchpoints_mut().remove(0));
+    assert!(cpu.watchpoints_mut().remove(1));
+    assert!(cpu.watchpoints().is_empty());
+    cpu.set_pc(0x204);
+    run(&mut cpu, 1);
+    assert!(cpu.watchpoints_mut().take_hits().is_empty());
+}
@@ -3,66 +3,355 @@ extern crate sdl2;
 
 use sdl2::Sdl;
 use sdl2::video::Window;
-use sdl2::render::Canvas;
+use sdl2::render::{BlendMode, Canvas};
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+
+use chip8_core::audio::{AudioConfig, AudioSink, Oscillator, Pattern};
+
+use crate::overlay;
+
+const LORES_WIDTH: u32 = 64;
+const LORES_HEIGHT: u32 = 32;
+// seconds between vertical blanks
+const FRAME_SECONDS: f32 = 1.0 / 60.0;
+
+// Colours indexed by the XO-CHIP bitplanes set in a pixel: 0 is the background,
+// 1-3 cover the usual two planes and the rest are only reachable with four planes
+const DEFAULT_PALETTE: [Color;16] =
+[
+    Color { r: 0x00, g: 0x00, b: 0x00, a: 0xFF },
+    Color { r: 0xFF, g: 0xFF, b: 0xFF, a: 0xFF },
+    Color { r: 0xAA, g: 0xAA, b: 0xAA, a: 0xFF },
+    Color { r: 0x55, g: 0x55, b: 0x55, a: 0xFF },
+    Color { r: 0xFF, g: 0x00, b: 0x00, a: 0xFF },
+    Color { r: 0x00, g: 0xFF, b: 0x00, a: 0xFF },
+    Color { r: 0x00, g: 0x00, b: 0xFF, a: 0xFF },
+    Color { r: 0xFF, g: 0xFF, b: 0x00, a: 0xFF },
+    Color { r: 0x88, g: 0x00, b: 0x00, a: 0xFF },
+    Color { r: 0x00, g: 0x88, b: 0x00, a: 0xFF },
+    Color { r: 0x00, g: 0x00, b: 0x88, a: 0xFF },
+    Color { r: 0x88, g: 0x88, b: 0x00, a: 0xFF },
+    Color { r: 0xFF, g: 0x00, b: 0xFF, a: 0xFF },
+    Color { r: 0x00, g: 0xFF, b: 0xFF, a: 0xFF },
+    Color { r: 0x88, g: 0x00, b: 0x88, a: 0xFF },
+    Color { r: 0x00, g: 0x88, b: 0x88, a: 0xFF },
+];
+
+/*
+    How the CHIP-8 screen is turned into what the window shows. DXYN XORs
+    sprites, so games move one by erasing and redrawing it and its pixels can
+    be dark at the end of every other frame. A CRT's phosphor kept glowing in
+    between, these bring some of that back. All off shows the screen as is.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayConfig {
+    // seconds a pixel takes to fade out after going dark, 0 turns it off at once
+    pub fade: f32,
+    // show each frame mixed half and half with the one before
+    pub blend: bool,
+    // only change the picture at the end of a frame, never halfway through
+    // one when the debugger stops there
+    pub vblank_only: bool,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> DisplayConfig {
+        DisplayConfig { fade: 0.0, blend: false, vblank_only: false }
+    }
+}
+
+/*
+    The display pipeline: the latest screen from the cpu, what is shown of it
+    and the afterglow of pixels that went dark, all one byte or float per pixel
+*/
+struct Display {
+    config: DisplayConfig,
+    width: usize,
+    height: usize,
+    // latest screen handed over by the cpu
+    gfx: Vec<u8>,
+    // the screen being shown, gfx as of the last vblank in vblank_only mode
+    shown: Vec<u8>,
+    // shown at the last vblank and the one before, for blending
+    completed: Vec<u8>,
+    previous: Vec<u8>,
+    // brightness left in each pixel and the planes it was lit with
+    glow: Vec<f32>,
+    glow_planes: Vec<u8>,
+}
+
+impl Display {
+    fn new(config: DisplayConfig, width: usize, height: usize) -> Display {
+        let size: usize = width * height;
+        Display {
+            config,
+            width,
+            height,
+            gfx: vec![0;size],
+            shown: vec![0;size],
+            completed: vec![0;size],
+            previous: vec![0;size],
+            glow: vec![0.0;size],
+            glow_planes: vec![0;size],
+        }
+    }
+
+    fn update(&mut self, gfx: &[u8], resolution: (usize, usize)) {
+        // switching between lores and hires starts over, nothing carries across
+        if resolution != (self.width, self.height) {
+            *self = Display::new(self.config, resolution.0, resolution.1);
+        }
+        self.gfx.copy_from_slice(gfx);
+        if !self.config.vblank_only {
+            self.shown.copy_from_slice(gfx);
+        }
+    }
+
+    /*
+        A frame ended. Latches the screen in vblank_only mode and moves the
+        afterglow and blending on by a frame. True when the picture may have
+        changed.
+    */
+    fn vblank(&mut self) -> bool {
+        if self.config.vblank_only {
+            self.shown.copy_from_slice(&self.gfx);
+        }
+        std::mem::swap(&mut self.previous, &mut self.completed);
+        self.completed.copy_from_slice(&self.shown);
+
+        let step: f32 = if self.config.fade > 0.0 { FRAME_SECONDS / self.config.fade } else { 1.0 };
+        for (index, &pixel) in self.shown.iter().enumerate() {
+            let planes: u8 = pixel & 0x0F;
+            if planes != 0 {
+                self.glow[index] = 1.0;
+                self.glow_planes[index] = planes;
+            } else {
+                self.glow[index] = (self.glow[index] - step).max(0.0);
+            }
+        }
+        self.config.vblank_only || self.config.blend || self.config.fade > 0.0
+    }
+
+    fn colour(&self, index: usize, palette: &[Color;16]) -> Color {
+        let planes: u8 = self.shown[index] & 0x0F;
+        let colour: Color = if planes == 0 && self.config.fade > 0.0 && self.glow[index] > 0.0 {
+            mix(palette[self.glow_planes[index] as usize], palette[0], self.glow[index])
+        } else {
+            palette[planes as usize]
+        };
+        if self.config.blend {
+            mix(colour, palette[(self.previous[index] & 0x0F) as usize], 0.5)
+        } else {
+            colour
+        }
+    }
+}
+
+// weight of a, between 0 and 1
+fn mix(a: Color, b: Color, weight: f32) -> Color {
+    let channel = |a: u8, b: u8| (a as f32 * weight + b as f32 * (1.0 - weight)).round() as u8;
+    Color::RGB(channel(a.r, b.r), channel(a.g, b.g), channel(a.b, b.b))
+}
+
+/*
+    SDL pulls samples from the oscillator on its own audio thread
+*/
+struct Beeper {
+    oscillator: Oscillator,
+}
+
+impl AudioCallback for Beeper {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = self.oscillator.next_sample();
+        }
+    }
+}
+
+/*
+    Emulator controls that aren't part of the CHIP-8 keypad
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hotkey {
+    // Shift + F1-F9
+    SaveState(u8),
+    // F1-F9
+    LoadState(u8),
+    // F10
+    TogglePause,
+    // F11
+    Step,
+    // F12
+    StepOver,
+    // Tab, shows or hides the debugger overlay
+    ToggleOverlay,
+    // P
+    NextPalette,
+    // Escape or closing the window
+    Quit,
+}
 
 pub struct App {
     sdl: Sdl,
+    audio: Option<AudioDevice<Beeper>>,
     canvas: Canvas<Window>,
-    gfx: [u8;2048],
-    keystate: [u8;16]
+    window_width: u32,
+    display: Display,
+    palette: [Color;16],
+    keystate: [u8;16],
+    hotkeys: Vec<Hotkey>,
+    rewinding: bool,
+    overlay: Vec<String>
 }
 
 impl App {
 
-    pub fn new() -> App {
+    /*
+        scale is the size in window pixels of a lores CHIP-8 pixel. Odd scales
+        are rounded up so hires pixels, half the size, fill the window exactly.
+    */
+    pub fn new(scale: u32, audio_config: AudioConfig) -> App {
+        let scale: u32 = scale + scale % 2;
         let sdl = sdl2::init().unwrap();
         let video_subsystem = sdl.video().unwrap();
         let window = video_subsystem
-            .window("chip-8-window", 640, 320)
+            .window("chip-8-window", LORES_WIDTH * scale, LORES_HEIGHT * scale)
             .build()
             .unwrap();
 
         let mut canvas = window.into_canvas().present_vsync().build().unwrap();
+        // the overlay is drawn translucent over the game
+        canvas.set_blend_mode(BlendMode::Blend);
         canvas.set_draw_color(Color::BLACK);
         canvas.clear();
 
+        // no sound is better than no emulator, carry on silently if there is no audio device
+        let audio = match App::open_audio(&sdl, audio_config) {
+            Ok(device) => {
+                // the device always runs, the oscillator outputs silence between beeps
+                device.resume();
+                Some(device)
+            }
+            Err(e) => {
+                eprintln!("Audio disabled: {}", e);
+                None
+            }
+        };
+
         App {
-            sdl: sdl,
-            canvas: canvas,
-            gfx: [0;2048],
-            keystate: [0;16]
+            sdl,
+            audio,
+            canvas,
+            window_width: LORES_WIDTH * scale,
+            display: Display::new(DisplayConfig::default(), LORES_WIDTH as usize, LORES_HEIGHT as usize),
+            palette: DEFAULT_PALETTE,
+            keystate: [0;16],
+            hotkeys: Vec::new(),
+            rewinding: false,
+            overlay: Vec::new()
         }
 
     }
 
+    fn open_audio(sdl: &Sdl, config: AudioConfig) -> Result<AudioDevice<Beeper>, String> {
+        let audio_subsystem = sdl.audio()?;
+        let desired_spec = AudioSpecDesired {
+            freq: Some(44_100),
+            channels: Some(1),
+            samples: None,
+        };
+        audio_subsystem.open_playback(None, &desired_spec, |spec| {
+            Beeper { oscillator: Oscillator::new(config, spec.freq as f32) }
+        })
+    }
+
+    /*
+        Replace the first colours of the palette, the rest keep their defaults
+    */
+    pub fn set_palette(&mut self, colours: &[Color]) {
+        self.palette = DEFAULT_PALETTE;
+        for (index, colour) in colours.iter().take(self.palette.len()).enumerate() {
+            self.palette[index] = *colour;
+        }
+    }
+
+    pub fn set_display(&mut self, config: DisplayConfig) {
+        self.display = Display::new(config, self.display.width, self.display.height);
+    }
+
+    pub fn get_audio_config(&mut self) -> Option<AudioConfig> {
+        self.audio.as_mut().map(|device| device.lock().oscillator.config)
+    }
+
+    pub fn set_audio_config(&mut self, config: AudioConfig) {
+        if let Some(device) = self.audio.as_mut() {
+            device.lock().oscillator.config = config;
+        }
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        if let Some(mut config) = self.get_audio_config() {
+            config.muted = muted;
+            self.set_audio_config(config);
+        }
+    }
+
     pub fn render(&mut self) {
         // use graphics::*;
-        self.canvas.set_draw_color(Color::BLACK);
+        self.canvas.set_draw_color(self.palette[0]);
         self.canvas.clear();
 
-        self.canvas.set_draw_color(Color::WHITE);
-
-        for y_coord in 0..32 {
-            for x_coord in 0..64 {
-                if self.gfx[x_coord + (y_coord * 64)] == 1 {
-                    match self.canvas.fill_rect(Rect::new((x_coord * 10) as i32, (y_coord * 10) as i32, 10, 10)) {
-                        Err(e) => panic!("Error drawing to canvas: {}", e),
-                        _=>()
+        // hires pixels are half the size of lores ones
+        let scale: u32 = self.window_width / (self.display.width as u32);
+
+        for y_coord in 0..self.display.height {
+            for x_coord in 0..self.display.width {
+                let colour: Color = self.display.colour(x_coord + (y_coord * self.display.width), &self.palette);
+                if colour != self.palette[0] {
+                    self.canvas.set_draw_color(colour);
+                    let rect = Rect::new((x_coord as u32 * scale) as i32, (y_coord as u32 * scale) as i32, scale, scale);
+                    if let Err(e) = self.canvas.fill_rect(rect) {
+                        panic!("Error drawing to canvas: {}", e);
                     }
                 }
             }
         }
 
+        if !self.overlay.is_empty() {
+            // font pixels a fifth of a lores pixel, at least one window pixel
+            let size: u32 = (self.window_width / LORES_WIDTH / 5).max(1);
+            if let Err(e) = overlay::draw_lines(&mut self.canvas, &self.overlay, size) {
+                panic!("Error drawing to canvas: {}", e);
+            }
+        }
+
         self.canvas.present();
 
     }
 
-    pub fn update(&mut self, gfx: &[u8;2048]) {
-        self.gfx = *gfx;
+    /*
+        Text drawn over the game by render, empty hides it
+    */
+    pub fn set_overlay(&mut self, lines: Vec<String>) {
+        self.overlay = lines;
+    }
+
+    pub fn update(&mut self, gfx: &[u8], resolution: (usize, usize)) {
+        self.display.update(gfx, resolution);
+    }
+
+    /*
+        Call at the end of every frame the cpu ran, true when the window needs
+        redrawing even if the cpu drew nothing
+    */
+    pub fn vblank(&mut self) -> bool {
+        self.display.vblank()
     }
 
     pub fn get_keystate(&mut self) -> [u8;16] {
@@ -166,6 +455,13 @@ impl App {
                     self.keystate[15] = 1;
                 }
 
+                Event::KeyDown {
+                    keycode: Some(Keycode::M), repeat: false, ..
+                } => {
+                    let muted: bool = self.get_audio_config().is_some_and(|config| config.muted);
+                    self.set_muted(!muted);
+                }
+
                 Event::KeyUp {
                     keycode: Some(Keycode::Num0), ..
                 } => {
@@ -263,123 +559,118 @@ impl App {
                 }
 
 
-                _=> {}
-            }
-        }
-
-        // for i in 0..keystate.len() {
-        //     print!("{} ", keystate[i]);
-        // }
-        // println!();
-
-        self.keystate
-    }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace), ..
+                } => {
+                    self.rewinding = true;
+                }
 
-    pub fn await_keypress(&mut self) -> u8 {
-        let mut event_pump = self.sdl.event_pump().unwrap();
-        loop {
-            for event in event_pump.poll_iter() {
-                // clean up this ugly code later
-                match event {
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Num0), ..
-                    } => {
-                        return 0x30;
-                    }
+                Event::KeyUp {
+                    keycode: Some(Keycode::Backspace), ..
+                } => {
+                    self.rewinding = false;
+                }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Num1), ..
-                    } => {
-                        return 0x31;
-                    }
+                Event::Quit { .. } | Event::KeyDown {
+                    keycode: Some(Keycode::Escape), ..
+                } => {
+                    self.hotkeys.push(Hotkey::Quit);
+                }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Num2), ..
-                    } => {
-                        return 0x32;
-                    }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F10), repeat: false, ..
+                } => {
+                    self.hotkeys.push(Hotkey::TogglePause);
+                }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Num3), ..
-                    } => {
-                        return 0x33;
-                    }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F11), ..
+                } => {
+                    self.hotkeys.push(Hotkey::Step);
+                }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Num4), ..
-                    } => {
-                        return 0x34;
-                    }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F12), ..
+                } => {
+                    self.hotkeys.push(Hotkey::StepOver);
+                }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Num5), ..
-                    } => {
-                        return 0x35;
-                    }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Tab), repeat: false, ..
+                } => {
+                    self.hotkeys.push(Hotkey::ToggleOverlay);
+                }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Num6), ..
-                    } => {
-                        return 0x36;
-                    }
+                Event::KeyDown {
+                    keycode: Some(Keycode::P), repeat: false, ..
+                } => {
+                    self.hotkeys.push(Hotkey::NextPalette);
+                }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Num7), ..
-                    } => {
-                        return 0x37;
+                Event::KeyDown {
+                    keycode: Some(keycode), keymod, repeat: false, ..
+                } if slot_for_key(keycode).is_some() => {
+                    let slot: u8 = slot_for_key(keycode).unwrap_or(1);
+                    if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                        self.hotkeys.push(Hotkey::SaveState(slot));
+                    } else {
+                        self.hotkeys.push(Hotkey::LoadState(slot));
                     }
+                }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Num8), ..
-                    } => {
-                        return 0x38;
-                    }
+                _=> {}
+            }
+        }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::Num9), ..
-                    } => {
-                        return 0x39;
-                    }
+        // for i in 0..keystate.len() {
+        //     print!("{} ", keystate[i]);
+        // }
+        // println!();
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::A), ..
-                    } => {
-                        return 0x61;
-                    }
+        self.keystate
+    }
+}
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::B), ..
-                    } => {
-                        return 0x62;
-                    }
+impl App {
+    /*
+        Hotkeys pressed since the last call, gathered by get_keystate
+    */
+    pub fn take_hotkeys(&mut self) -> Vec<Hotkey> {
+        std::mem::take(&mut self.hotkeys)
+    }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::C), ..
-                    } => {
-                        return 0x63;
-                    }
+    // true while Backspace is held
+    pub fn is_rewinding(&self) -> bool {
+        self.rewinding
+    }
+}
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::D), ..
-                    } => {
-                        return 0x64;
-                    }
+// save slots 1-9 on F1-F9
+fn slot_for_key(keycode: Keycode) -> Option<u8> {
+    match keycode {
+        Keycode::F1 => Some(1),
+        Keycode::F2 => Some(2),
+        Keycode::F3 => Some(3),
+        Keycode::F4 => Some(4),
+        Keycode::F5 => Some(5),
+        Keycode::F6 => Some(6),
+        Keycode::F7 => Some(7),
+        Keycode::F8 => Some(8),
+        Keycode::F9 => Some(9),
+        _ => None,
+    }
+}
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::E), ..
-                    } => {
-                        return 0x65;
-                    }
+impl AudioSink for App {
+    fn set_playing(&mut self, _frame: u64, playing: bool) {
+        if let Some(device) = self.audio.as_mut() {
+            device.lock().oscillator.playing = playing;
+        }
+    }
 
-                    Event::KeyDown {
-                        keycode: Some(Keycode::F), ..
-                    } => {
-                        return 0x66;
-                    }
-    
-                    _=> {}
-                }
-            }
+    fn set_pattern(&mut self, _frame: u64, pattern: Option<Pattern>) {
+        if let Some(device) = self.audio.as_mut() {
+            device.lock().oscillator.pattern = pattern;
         }
     }
 }
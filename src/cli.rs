@@ -0,0 +1,222 @@
+use chip8_core::audio::{AudioConfig, Waveform};
+use chip8_core::chip8::Mode;
+use chip8_core::quirks::Quirks;
+use chip8_core::random::RandomKind;
+use chip8_core::trace::{TraceFilter, TraceFormat};
+
+use crate::app::DisplayConfig;
+
+pub const USAGE: &str = "usage: chip_8_emulator <rom> [options]
+
+  --speed N           instructions per second (default 700)
+  --scale N           window pixels per CHIP-8 pixel, odd values round up (default 10)
+  --quirks NAME       vip, chip48, schip, xochip or modern (default depends on --mode)
+  --mode NAME         chip8, schip or xochip (default chip8)
+  --seed N            seed the random number generator used by CXNN
+  --rng NAME          xorshift or lcg (default xorshift)
+  --mute              start with sound muted (toggle with M)
+  --volume N          beep volume from 0.0 to 1.0 (default 0.25)
+  --tone HZ           beep frequency (default 440)
+  --waveform NAME     square, sine, triangle or sawtooth (default square)
+  --palette NAME      classic, lcd, amber, hp48, octo, one from the palette file, or comma
+                      separated rrggbb colours: background, plane 1, plane 2, ... (P cycles them)
+  --palettes FILE     read more palettes from FILE (default ~/.config/chip8/palettes)
+  --fade MS           let pixels fade out over MS milliseconds like a CRT's phosphor (default 0, off)
+  --blend             show every frame mixed with the previous one, hides flicker
+  --vblank            only update the picture at the end of a frame
+  --rewind SECS       seconds of history kept for rewinding with Backspace (default 10, 0 disables)
+  --record FILE       record the keypad input to a movie file for replaying later
+  --play FILE         replay a movie file, it sets the speed, mode, quirks, seed and rng
+  --debug             start paused and read debugger commands from the terminal (type help)
+  --gdb PORT          accept a gdb remote protocol connection on localhost PORT
+  --headless          run without a window and print the final screen
+  --frames N          stop after N frames (headless default 600, or the movie's length)
+  --trace FILE        write a record of every instruction run to FILE
+  --trace-format NAME text or binary (default text)
+  --trace-pc RANGE    only trace instructions at these addresses, e.g. 200-2ff (hex)
+  --trace-ops DIGITS  only trace opcodes starting with these hex digits, e.g. 8,d
+  --trace-frames A-B  only trace frames A to B, B may be left out
+  -h, --help          show this message";
+
+pub struct Options {
+    pub rom: String,
+    pub speed: u32,
+    pub scale: u32,
+    pub quirks: Quirks,
+    pub mode: Mode,
+    pub seed: Option<u64>,
+    pub rng: RandomKind,
+    pub audio: AudioConfig,
+    pub palette: Option<String>,
+    pub palettes: Option<String>,
+    pub display: DisplayConfig,
+    pub rewind_seconds: f32,
+    pub record: Option<String>,
+    pub play: Option<String>,
+    pub debug: bool,
+    pub gdb: Option<u16>,
+    pub headless: bool,
+    pub frames: Option<u64>,
+    pub trace: Option<String>,
+    pub trace_format: TraceFormat,
+    pub trace_filter: TraceFilter,
+}
+
+pub enum Command {
+    Help,
+    Run(Box<Options>),
+}
+
+/*
+    Parse the arguments after the program name. Errors are messages meant for the user.
+*/
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let mut rom: Option<String> = None;
+    let mut speed: u32 = 700;
+    let mut scale: u32 = 10;
+    let mut quirks: Option<Quirks> = None;
+    let mut mode: Mode = Mode::Chip8;
+    let mut seed: Option<u64> = None;
+    let mut rng: RandomKind = RandomKind::Xorshift;
+    let mut audio: AudioConfig = AudioConfig::default();
+    let mut palette: Option<String> = None;
+    let mut palettes: Option<String> = None;
+    let mut display: DisplayConfig = DisplayConfig::default();
+    let mut rewind_seconds: f32 = 10.0;
+    let mut record: Option<String> = None;
+    let mut play: Option<String> = None;
+    let mut debug: bool = false;
+    let mut gdb: Option<u16> = None;
+    let mut headless: bool = false;
+    let mut frames: Option<u64> = None;
+    let mut trace: Option<String> = None;
+    let mut trace_format: TraceFormat = TraceFormat::Text;
+    let mut trace_filter: TraceFilter = TraceFilter::default();
+
+    let mut index: usize = 0;
+    while index < args.len() {
+        let arg: &str = &args[index];
+        // every option except the flags takes the next argument as its value
+        let mut value = || -> Result<&str, String> {
+            index += 1;
+            match args.get(index) {
+                Some(value) => Ok(value),
+                None => Err(format!("{} needs a value", arg)),
+            }
+        };
+
+        match arg {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--speed" => {
+                speed = parse_number(value()?, arg)?;
+                if speed == 0 {
+                    return Err(String::from("--speed must be at least 1"));
+                }
+            }
+            "--scale" => {
+                scale = parse_number(value()?, arg)?;
+                if scale == 0 || scale > 40 {
+                    return Err(String::from("--scale must be between 1 and 40"));
+                }
+            }
+            "--quirks" => {
+                let name: &str = value()?;
+                quirks = Some(Quirks::from_name(name)
+                    .ok_or_else(|| format!("unknown quirks profile '{}', expected vip, chip48, schip, xochip or modern", name))?);
+            }
+            "--mode" => {
+                let name: &str = value()?;
+                mode = Mode::from_name(name)
+                    .ok_or_else(|| format!("unknown mode '{}', expected chip8, schip or xochip", name))?;
+            }
+            "--seed" => seed = Some(parse_number(value()?, arg)?),
+            "--rng" => {
+                let name: &str = value()?;
+                rng = RandomKind::from_name(name)
+                    .ok_or_else(|| format!("unknown random generator '{}', expected xorshift or lcg", name))?;
+            }
+            "--mute" => audio.muted = true,
+            "--volume" => {
+                audio.volume = parse_number(value()?, arg)?;
+                if !(0.0..=1.0).contains(&audio.volume) {
+                    return Err(String::from("--volume must be between 0.0 and 1.0"));
+                }
+            }
+            "--tone" => {
+                audio.frequency = parse_number(value()?, arg)?;
+                if audio.frequency <= 0.0 {
+                    return Err(String::from("--tone must be a positive frequency"));
+                }
+            }
+            "--waveform" => {
+                let name: &str = value()?;
+                audio.waveform = Waveform::from_name(name)
+                    .ok_or_else(|| format!("unknown waveform '{}', expected square, sine, triangle or sawtooth", name))?;
+            }
+            "--palette" => palette = Some(String::from(value()?)),
+            "--palettes" => palettes = Some(String::from(value()?)),
+            "--fade" => {
+                let milliseconds: u32 = parse_number(value()?, arg)?;
+                if milliseconds > 5000 {
+                    return Err(String::from("--fade must be at most 5000 milliseconds"));
+                }
+                display.fade = milliseconds as f32 / 1000.0;
+            }
+            "--blend" => display.blend = true,
+            "--vblank" => display.vblank_only = true,
+            "--rewind" => {
+                rewind_seconds = parse_number(value()?, arg)?;
+                if !(0.0..=600.0).contains(&rewind_seconds) {
+                    return Err(String::from("--rewind must be between 0 and 600 seconds"));
+                }
+            }
+            "--record" => record = Some(String::from(value()?)),
+            "--play" => play = Some(String::from(value()?)),
+            "--debug" => debug = true,
+            "--gdb" => gdb = Some(parse_number(value()?, arg)?),
+            "--headless" => headless = true,
+            "--frames" => frames = Some(parse_number(value()?, arg)?),
+            "--trace" => trace = Some(String::from(value()?)),
+            "--trace-format" => {
+                let name: &str = value()?;
+                trace_format = TraceFormat::from_name(name)
+                    .ok_or_else(|| format!("unknown trace format '{}', expected text or binary", name))?;
+            }
+            "--trace-pc" => trace_filter.addresses = Some(TraceFilter::parse_addresses(value()?)?),
+            "--trace-ops" => trace_filter.classes = Some(TraceFilter::parse_classes(value()?)?),
+            "--trace-frames" => trace_filter.frames = Some(TraceFilter::parse_frames(value()?)?),
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ if rom.is_none() => rom = Some(String::from(arg)),
+            _ => return Err(format!("unexpected argument '{}', only one rom can be run", arg)),
+        }
+        index += 1;
+    }
+
+    let rom: String = rom.ok_or_else(|| String::from("no rom given"))?;
+    if record.is_some() && play.is_some() {
+        return Err(String::from("--record and --play can't be used together"));
+    }
+    if debug && headless {
+        return Err(String::from("--debug needs a window"));
+    }
+    if gdb.is_some() && headless {
+        return Err(String::from("--gdb needs a window"));
+    }
+    if record.is_some() && headless {
+        return Err(String::from("--record needs a window to take input from"));
+    }
+
+    // without an explicit profile pick the one matching the instruction set
+    let quirks: Quirks = quirks.unwrap_or(match mode {
+        Mode::Chip8 => Quirks::default(),
+        Mode::SuperChip => Quirks::SUPER_CHIP,
+        Mode::XoChip => Quirks::XO_CHIP,
+    });
+
+    Ok(Command::Run(Box::new(Options { rom, speed, scale, quirks, mode, seed, rng, audio, palette, palettes, display, rewind_seconds, record, play, debug, gdb, headless, frames,
+        trace, trace_format, trace_filter })))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} expects a number, got '{}'", flag, value))
+}
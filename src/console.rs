@@ -0,0 +1,40 @@
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+/*
+    Reads debugger commands from the terminal. stdin blocks, so lines are read
+    on their own thread and picked up by the emulation loop between frames.
+*/
+pub struct Console {
+    lines: Receiver<String>,
+}
+
+impl Console {
+    pub fn start() -> Console {
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+                // the receiver is gone once the emulator quits
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Console { lines }
+    }
+
+    /*
+        Lines entered since the last call, skipping blank ones
+    */
+    pub fn poll(&self) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        loop {
+            match self.lines.try_recv() {
+                Ok(line) if line.trim().is_empty() => {}
+                Ok(line) => lines.push(line),
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        lines
+    }
+}
@@ -1,28 +1,345 @@
-
-mod chip8;
 mod app;
+mod cli;
+mod console;
+mod overlay;
+mod palette;
+
+use std::time::{Duration, Instant};
+
+use chip8_core::audio::AudioSink;
+use chip8_core::chip8::Chip8;
+use chip8_core::debugger::{self, DebugCommand, DebugEvent, Debugger};
+use chip8_core::gdb::{GdbEvent, GdbServer};
+use chip8_core::headless::{HeadlessRunner, StopCondition, StopReason};
+use chip8_core::movie::Movie;
+use chip8_core::rewind::RewindBuffer;
+use chip8_core::trace::Tracer;
+
+use palette::Theme;
+
+// timers and the screen run at 60 Hz
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+const DEFAULT_HEADLESS_FRAMES: u64 = 600;
 
 fn main() {
 
-    let mut cpu = chip8::Chip8::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options: cli::Options = match cli::parse(&args) {
+        Ok(cli::Command::Run(options)) => *options,
+        Ok(cli::Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let replay: Option<Movie> = options.play.as_ref().map(|path| match read_movie(path) {
+        Ok(movie) => movie,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }
+    });
+
+    let mut cpu = Chip8::new(options.quirks);
+    cpu.set_mode(options.mode);
+    cpu.set_speed(options.speed);
+    if let Some(seed) = options.seed {
+        cpu.seed_rng(seed);
+    }
+    cpu.set_random_kind(options.rng);
+    if let Some(movie) = &replay {
+        movie.configure(&mut cpu);
+    }
     cpu.initialize();
-    cpu.load(String::from("src/programs/UFO"));
+    if let Err(e) = cpu.load(options.rom.clone()) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    if let Some(Err(e)) = replay.as_ref().map(|movie| movie.check_rom(&cpu)) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    if let Some(path) = &options.trace {
+        match Tracer::create(path, options.trace_format, options.trace_filter.clone()) {
+            Ok(tracer) => cpu.set_tracer(Some(tracer)),
+            Err(e) => {
+                eprintln!("Failed to create {}: {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if options.headless {
+        let default_frames: u64 = replay.as_ref().map_or(DEFAULT_HEADLESS_FRAMES, |movie| movie.length());
+        run_headless(cpu, options.frames.unwrap_or(default_frames), replay);
+    } else {
+        let themes: Vec<Theme> = palette::load(options.palettes.as_deref()).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        let theme: Theme = match &options.palette {
+            Some(value) => palette::resolve(value, &themes).unwrap_or_else(|e| {
+                eprintln!("error: {}", e);
+                std::process::exit(2);
+            }),
+            None => themes[0].clone(),
+        };
+        run_windowed(cpu, &options, replay, &themes, theme);
+    }
+
+}
+
+fn run_headless(cpu: Chip8, frames: u64, replay: Option<Movie>) {
+    let mut runner = HeadlessRunner::new(cpu);
+    runner.add_stop_condition(StopCondition::Frames(frames));
+    if let Some(movie) = replay {
+        runner.set_input(movie.input_script());
+    }
+    let reason: StopReason = runner.run();
+    print!("{}", runner.framebuffer_text());
+    eprintln!("Stopped after {} frames: {}", runner.cpu().get_frame_count(), reason);
+    finish_trace(runner.cpu_mut());
+    if let StopReason::Error(_) = reason {
+        eprintln!("{}", runner.cpu().dump_registers());
+        std::process::exit(1);
+    }
+}
 
-    let mut app = app::App::new();
+fn run_windowed(mut cpu: Chip8, options: &cli::Options, mut replay: Option<Movie>, themes: &[Theme], mut theme: Theme) {
+    let mut app = app::App::new(options.scale, options.audio);
+    app.set_palette(&theme.colours);
+    app.set_display(options.display);
     app.render();
 
-    loop {
-        cpu.emulate_cycle();
+    // set when the cpu hits an error or exits, keeps the window up so the last frame can be inspected
+    let mut halted = false;
+    let mut next_frame = Instant::now();
+    let mut rewind = RewindBuffer::with_seconds(options.rewind_seconds);
+    let mut recording: Option<Movie> = options.record.as_ref().map(|_| Movie::new(&cpu));
+    let mut debugger = Debugger::new();
+    let mut show_overlay = false;
+    let console: Option<console::Console> = if options.debug {
+        debugger.pause();
+        println!("Paused before the first instruction, type help for debugger commands");
+        Some(console::Console::start())
+    } else {
+        None
+    };
+    let mut gdb: Option<GdbServer> = options.gdb.map(|port| match GdbServer::listen(&format!("127.0.0.1:{}", port)) {
+        Ok(server) => {
+            println!("Waiting for gdb on localhost:{}", port);
+            server
+        }
+        Err(e) => {
+            eprintln!("Failed to listen on port {}: {}", port, e);
+            std::process::exit(1);
+        }
+    });
+
+    'running: loop {
+        // the keyboard is read even during a replay, hotkeys keep working
+        let mut keys: [u8;16] = app.get_keystate();
+        if let Some(movie) = &replay {
+            if cpu.get_frame_count() < movie.length() {
+                keys = movie.keys_at(cpu.get_frame_count());
+            } else {
+                println!("Replay finished, the keyboard is live again");
+                replay = None;
+            }
+        }
+        if let Some(movie) = &mut recording {
+            movie.record(cpu.get_frame_count(), &keys);
+        }
+        cpu.update_keystate(&keys);
+
+        for hotkey in app.take_hotkeys() {
+            match hotkey {
+                app::Hotkey::SaveState(slot) => save_slot(&cpu, &options.rom, slot),
+                app::Hotkey::LoadState(slot) => {
+                    if load_slot(&mut cpu, &options.rom, slot) {
+                        // a state from before the error is a way out of it
+                        halted = false;
+                        debugger.reset_frame();
+                    }
+                }
+                app::Hotkey::TogglePause => {
+                    if debugger.is_paused() {
+                        debugger.resume();
+                    } else {
+                        debugger.pause();
+                        println!("{}", debugger::register_view(&cpu).join("\n"));
+                    }
+                }
+                app::Hotkey::Step => debugger.step(),
+                app::Hotkey::StepOver => debugger.step_over(&cpu),
+                app::Hotkey::ToggleOverlay => {
+                    show_overlay = !show_overlay;
+                    if !show_overlay {
+                        app.set_overlay(Vec::new());
+                        app.render();
+                    }
+                }
+                app::Hotkey::NextPalette => {
+                    // a palette given as colours isn't in the list, cycling starts over
+                    let next: usize = themes.iter().position(|existing| existing.name == theme.name).map_or(0, |index| (index + 1) % themes.len());
+                    theme = themes[next].clone();
+                    app.set_palette(&theme.colours);
+                    app.render();
+                    println!("Palette: {}", theme.name);
+                }
+                app::Hotkey::Quit => break 'running,
+            }
+        }
+
+        if let Some(console) = &console {
+            for line in console.poll() {
+                match DebugCommand::parse(&line) {
+                    Ok(command) => {
+                        let output: String = debugger.execute(command, &mut cpu);
+                        if !output.is_empty() {
+                            println!("{}", output);
+                        }
+                    }
+                    Err(e) => println!("{}", e),
+                }
+            }
+        }
+
+        if let Some(server) = &mut gdb {
+            for event in server.poll(&mut debugger, &mut cpu, halted) {
+                println!("{}", event);
+                if event == GdbEvent::Kill {
+                    break 'running;
+                }
+            }
+        }
+
+        // a frame ending is the display's vertical blank, rewinding counts too
+        let frame: u64 = cpu.get_frame_count();
+        if app.is_rewinding() {
+            // step back one frame per frame, so holding the key plays the game backwards
+            if let Some(state) = rewind.pop() {
+                if cpu.load_state(&state).is_ok() {
+                    halted = false;
+                    debugger.reset_frame();
+                }
+            }
+        } else if !halted {
+            match debugger.run_frame(&mut cpu) {
+                Err(e) => {
+                    eprintln!("Emulation halted: {}", e);
+                    eprintln!("{}", cpu.dump_registers());
+                    halted = true;
+                }
+                Ok(_) if cpu.has_exited() => {
+                    println!("Program exited");
+                    halted = true;
+                }
+                Ok(DebugEvent::Frame) => rewind.push(cpu.save_state()),
+                Ok(DebugEvent::Break(reason)) => {
+                    println!("{}\n{}", reason, debugger::register_view(&cpu).join("\n"));
+                }
+                Ok(DebugEvent::Idle) => {}
+            }
+        }
+
+        for hit in cpu.watchpoints_mut().take_hits() {
+            println!("{}", hit);
+        }
 
-        if cpu.check_draw_sema() {
-            app.update(&cpu.get_gfx());
+        app.set_pattern(cpu.get_frame_count(), cpu.get_sound_pattern());
+        app.set_playing(cpu.get_frame_count(), cpu.is_sound_playing());
+
+        if show_overlay {
+            let mut lines: Vec<String> = debugger::register_view(&cpu);
+            if debugger.is_paused() {
+                lines.push(String::from("PAUSED"));
+            }
+            app.set_overlay(lines);
+        }
+
+        let drawn: bool = cpu.check_draw_sema();
+        if drawn {
+            app.update(cpu.get_gfx(), cpu.get_resolution());
+        }
+        let faded: bool = cpu.get_frame_count() != frame && app.vblank();
+        // the overlay follows the registers even while the screen doesn't change
+        if drawn || faded || show_overlay {
             app.render();
         }
 
-        // if cpu.check_key_sema() {
-        //     println!("key pressed was: {}", app.await_keypress());
-        // }
-        cpu.update_keystate(&app.get_keystate());
+        if options.frames.is_some_and(|frames| cpu.get_frame_count() >= frames) {
+            break;
+        }
+
+        // frame limiter, after a stall start counting again from now instead of rushing to catch up
+        next_frame += FRAME_DURATION;
+        let now = Instant::now();
+        if next_frame > now {
+            std::thread::sleep(next_frame - now);
+        } else {
+            next_frame = now;
+        }
     }
 
-}
\ No newline at end of file
+    if let (Some(movie), Some(path)) = (recording, &options.record) {
+        match std::fs::write(path, movie.to_bytes()) {
+            Ok(()) => println!("Recorded {} frames to {}", movie.length(), path),
+            Err(e) => eprintln!("Failed to write {}: {}", path, e),
+        }
+    }
+    finish_trace(&mut cpu);
+}
+
+fn finish_trace(cpu: &mut Chip8) {
+    if let Some(tracer) = cpu.take_tracer() {
+        let records: u64 = tracer.records();
+        match tracer.finish() {
+            Ok(()) => println!("Traced {} instructions", records),
+            Err(e) => eprintln!("Failed to write the trace: {}", e),
+        }
+    }
+}
+
+fn read_movie(path: &str) -> Result<Movie, String> {
+    let bytes: Vec<u8> = std::fs::read(path).map_err(|e| e.to_string())?;
+    Movie::from_bytes(&bytes).map_err(|e| e.to_string())
+}
+
+// save slots live next to the rom as <rom>.state<slot>
+fn slot_path(rom: &str, slot: u8) -> String {
+    format!("{}.state{}", rom, slot)
+}
+
+fn save_slot(cpu: &Chip8, rom: &str, slot: u8) {
+    let path: String = slot_path(rom, slot);
+    match std::fs::write(&path, cpu.save_state()) {
+        Ok(()) => println!("Saved state to slot {}", slot),
+        Err(e) => eprintln!("Failed to write {}: {}", path, e),
+    }
+}
+
+fn load_slot(cpu: &mut Chip8, rom: &str, slot: u8) -> bool {
+    let path: String = slot_path(rom, slot);
+    let data: Vec<u8> = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            return false;
+        }
+    };
+    match cpu.load_state(&data) {
+        Ok(()) => {
+            println!("Loaded state from slot {}", slot);
+            true
+        }
+        Err(e) => {
+            eprintln!("Slot {}: {}", slot, e);
+            false
+        }
+    }
+}
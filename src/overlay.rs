@@ -0,0 +1,92 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+/*
+    Tiny 3x5 pixel font for drawing debugger text over the game, enough for
+    upper case letters, digits and a little punctuation. Each row is three
+    bits, the high bit on the left.
+*/
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+
+fn glyph(character: char) -> [u8;5] {
+    match character.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b011, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '?' => [0b110, 0b001, 0b010, 0b000, 0b010],
+        _ => [0;5],
+    }
+}
+
+/*
+    Draw lines of text in the top left corner on a translucent backing box.
+    size is the number of window pixels per font pixel.
+*/
+pub fn draw_lines(canvas: &mut Canvas<Window>, lines: &[String], size: u32) -> Result<(), String> {
+    let advance: u32 = (GLYPH_WIDTH + 1) * size;
+    let line_height: u32 = (GLYPH_HEIGHT + 2) * size;
+    let columns: u32 = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u32;
+    if columns == 0 {
+        return Ok(());
+    }
+
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 0xC0));
+    canvas.fill_rect(Rect::new(0, 0, columns * advance + 2 * size, lines.len() as u32 * line_height + size))?;
+
+    canvas.set_draw_color(Color::RGB(0xFF, 0xCC, 0x00));
+    for (row, line) in lines.iter().enumerate() {
+        let top: u32 = size + row as u32 * line_height;
+        for (column, character) in line.chars().enumerate() {
+            let left: u32 = size + column as u32 * advance;
+            for (glyph_row, bits) in glyph(character).iter().enumerate() {
+                for glyph_column in 0..GLYPH_WIDTH {
+                    if bits & (0b100 >> glyph_column) != 0 {
+                        let x: u32 = left + glyph_column * size;
+                        let y: u32 = top + glyph_row as u32 * size;
+                        canvas.fill_rect(Rect::new(x as i32, y as i32, size, size))?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
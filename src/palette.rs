@@ -0,0 +1,134 @@
+use std::path::PathBuf;
+
+use sdl2::pixels::Color;
+
+/*
+    Named palettes. The colours are indexed by the XO-CHIP bitplanes set in a
+    pixel: the background, plane 1, plane 2, both planes, and up to 16 for
+    four planes. Colours a palette leaves out keep the defaults.
+
+    Besides the built in themes more can be defined in a file, one per line:
+
+        # comments and blank lines are skipped
+        gameboy = 0f380f, 8bac0f, 306230, 9bbc0f
+        classic = 101010, e0e0e0
+
+    A palette named like a built in one replaces it. The file is the one given
+    with --palettes, otherwise $XDG_CONFIG_HOME/chip8/palettes (or
+    ~/.config/chip8/palettes) when it exists.
+*/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub colours: Vec<Color>,
+}
+
+const BUILT_IN: [(&str, &[u32]);5] = [
+    ("classic", &[0x000000, 0xFFFFFF, 0xAAAAAA, 0x555555]),
+    ("lcd", &[0x9BBC0F, 0x0F380F, 0x8BAC0F, 0x306230]),
+    ("amber", &[0x140C00, 0xFFB000, 0x9C6B00, 0x5C3F00]),
+    ("hp48", &[0xA8B497, 0x1E2B24, 0x5E6B58, 0x3A4A3E]),
+    ("octo", &[0x996600, 0xFFCC00, 0xFF6600, 0x662200]),
+];
+
+pub fn built_in() -> Vec<Theme> {
+    BUILT_IN.iter().map(|(name, colours)| Theme {
+        name: String::from(*name),
+        colours: colours.iter().map(|&rgb| Color::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)).collect(),
+    }).collect()
+}
+
+/*
+    "000000,ffffff" style list of 2 to 16 colours
+*/
+pub fn parse_colours(value: &str) -> Result<Vec<Color>, String> {
+    let mut colours: Vec<Color> = Vec::new();
+    for entry in value.split(',') {
+        let hex: &str = entry.trim().trim_start_matches('#');
+        let rgb: u32 = match u32::from_str_radix(hex, 16) {
+            Ok(rgb) if hex.len() == 6 => rgb,
+            _ => return Err(format!("bad palette colour '{}', expected rrggbb", entry.trim())),
+        };
+        colours.push(Color::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+    }
+    if colours.len() < 2 || colours.len() > 16 {
+        return Err(String::from("a palette needs between 2 and 16 colours"));
+    }
+    Ok(colours)
+}
+
+/*
+    The palettes defined in a file's text, errors name the line
+*/
+pub fn parse_file(text: &str) -> Result<Vec<Theme>, String> {
+    let mut themes: Vec<Theme> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line: &str = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, colours) = line.split_once('=').ok_or_else(|| format!("line {}: expected name = colours", number + 1))?;
+        let name: &str = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("line {}: bad palette name '{}'", number + 1, name));
+        }
+        let colours: Vec<Color> = parse_colours(colours).map_err(|e| format!("line {}: {}", number + 1, e))?;
+        themes.push(Theme { name: String::from(name), colours });
+    }
+    Ok(themes)
+}
+
+// where the palette file is looked for when --palettes isn't given
+pub fn default_file() -> Option<PathBuf> {
+    let config: PathBuf = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(directory) if !directory.is_empty() => PathBuf::from(directory),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config.join("chip8").join("palettes"))
+}
+
+/*
+    The built in themes followed by the ones from the file, a file palette
+    with a built in name takes its place. Without a path the default file is
+    read if there is one.
+*/
+pub fn load(path: Option<&str>) -> Result<Vec<Theme>, String> {
+    let mut themes: Vec<Theme> = built_in();
+    // a palette file asked for has to be there, the default one doesn't
+    let (path, required): (PathBuf, bool) = match path {
+        Some(path) => (PathBuf::from(path), true),
+        None => match default_file() {
+            Some(path) => (path, false),
+            None => return Ok(themes),
+        },
+    };
+    let text: String = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => return Ok(themes),
+        Err(e) => return Err(format!("failed to read {}: {}", path.display(), e)),
+    };
+    for theme in parse_file(&text).map_err(|e| format!("{}: {}", path.display(), e))? {
+        match themes.iter_mut().find(|existing| existing.name.eq_ignore_ascii_case(&theme.name)) {
+            Some(existing) => *existing = theme,
+            None => themes.push(theme),
+        }
+    }
+    Ok(themes)
+}
+
+/*
+    What --palette names: a list of colours or one of the themes
+*/
+pub fn resolve(value: &str, themes: &[Theme]) -> Result<Theme, String> {
+    if value.contains(',') {
+        return Ok(Theme { name: String::from("custom"), colours: parse_colours(value)? });
+    }
+    match themes.iter().find(|theme| theme.name.eq_ignore_ascii_case(value)) {
+        Some(theme) => Ok(theme.clone()),
+        None => {
+            let names: Vec<&str> = themes.iter().map(|theme| theme.name.as_str()).collect();
+            Err(format!("unknown palette '{}', expected {} or a list of rrggbb colours", value, names.join(", ")))
+        }
+    }
+}